@@ -0,0 +1,433 @@
+#![allow(warnings)]
+//! Thesis mutations for AFL++ (custom mutator API) and libFuzzer (LLVMFuzzerCustomMutator).
+//!
+//! The queue / corpus holds the serialized input, the target gets the rendered bytes:
+//! - AFL++ renders through `afl_custom_post_process`
+//! - libFuzzer harnesses call `thesis_render` in `LLVMFuzzerTestOneInput`
+//!
+//! ```ignore
+//! impl_converter!(Code, |data: Code| ...);
+//! custom_mutator::export_mutator!(Code, FuzzDataTargetBytesConverter);
+//! ```
+//!
+//! Mutations over the budget are thrown away, it is read from `THESIS_MAX_NODES`,
+//! `THESIS_MAX_DEPTH` and `THESIS_MAX_LEN` (rendered bytes).
+use libafl::inputs::TargetBytesConverter;
+use libafl_bolts::{rands::Rand, AsSlice};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, VecDeque},
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    panic::AssertUnwindSafe,
+    sync::mpsc::{self, Receiver, Sender},
+};
+use thesis::{
+    budget::Budget, serialize, try_deserialize, DepthInfo, Id, MutationType, Node, NodeType,
+    Visitor,
+};
+
+/// how many chunks we keep per type
+const MAX_CHUNKS: usize = 512;
+/// how many mutations we stack per fuzz call
+const MAX_STACK: usize = 4;
+/// how often we retry if the mutated input is larger than allowed or over the budget
+const MAX_TRIES: usize = 8;
+/// We are called by afl-fuzz / libFuzzer, on their stack. Our traversals grow it as they go,
+/// but (de)serializing recurses in serde, so the mutator runs on a thread with a stack this size.
+const STACK_SIZE: usize = 1 << 30;
+
+/// A call for the mutator thread, it only lives until the caller got its answer
+struct Call<M>(*mut (dyn FnMut(&mut M) + 'static));
+
+unsafe impl<M> Send for Call<M> {}
+
+/// The mutator, on a thread of its own with a stack of `STACK_SIZE`.
+/// The thread is spawned once: a fresh stack per call would map (and fault in) a new one
+/// for every execution. Calls block until the mutator is done, so they can borrow.
+pub struct MutatorThread<M> {
+    calls: Sender<Call<M>>,
+    /// false if the call panicked
+    done: Receiver<bool>,
+}
+
+impl<M: 'static> MutatorThread<M> {
+    /// `make` builds the mutator on the new thread
+    pub fn new(make: impl FnOnce() -> M + Send + 'static) -> Self {
+        let (calls, receiver) = mpsc::channel::<Call<M>>();
+        let (sender, done) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("thesis-mutator".to_string())
+            .stack_size(STACK_SIZE)
+            .spawn(move || {
+                let mut mutator = make();
+                for call in receiver {
+                    let call = AssertUnwindSafe(|| unsafe { (*call.0)(&mut mutator) });
+                    let ok = std::panic::catch_unwind(call).is_ok();
+                    if sender.send(ok).is_err() {
+                        break;
+                    }
+                }
+            })
+            .expect("cannot spawn the mutator thread");
+        Self { calls, done }
+    }
+
+    /// Run `f` with the mutator and wait for it
+    pub fn run<R>(&self, f: impl FnOnce(&mut M) -> R) -> R {
+        let mut f = Some(f);
+        let mut result = None;
+        let mut call = |mutator: &mut M| result = Some((f.take().expect("Hc7wPq2n____"))(mutator));
+        let call: &mut (dyn FnMut(&mut M) + '_) = &mut call;
+        // we wait for the answer before `call` goes out of scope, so it never dangles
+        let call: *mut (dyn FnMut(&mut M) + 'static) = unsafe { std::mem::transmute(call) };
+        self.calls.send(Call(call)).expect("the mutator thread is gone");
+        if !self.done.recv().expect("the mutator thread is gone") {
+            panic!("the mutator panicked");
+        }
+        result.expect("Tn4vYb8k____")
+    }
+}
+
+pub struct GrammarMutator<I, TC> {
+    visitor: Visitor,
+    converter: TC,
+    /// serialized subtrees of the inputs we have seen, per type.
+    /// this is our (in memory) version of the fuzzer's chunks directory.
+    chunks: HashMap<Id, Vec<Vec<u8>>>,
+    budget: Budget,
+    out: Vec<u8>,
+    phantom: PhantomData<I>,
+}
+
+fn budget_from_env() -> Budget {
+    let var = |name: &str| std::env::var(name).ok().and_then(|value| value.parse().ok());
+    Budget {
+        max_nodes: var("THESIS_MAX_NODES"),
+        max_depth: var("THESIS_MAX_DEPTH"),
+        max_len: var("THESIS_MAX_LEN"),
+    }
+}
+
+impl<I, TC> GrammarMutator<I, TC>
+where
+    I: Node + Clone,
+    TC: TargetBytesConverter<Input = I>,
+{
+    pub fn new(seed: u64, converter: TC) -> Self {
+        Self {
+            visitor: Visitor::new(seed, DepthInfo::default()),
+            converter,
+            chunks: HashMap::default(),
+            budget: budget_from_env(),
+            out: vec![],
+            phantom: PhantomData,
+        }
+    }
+
+    /// libFuzzer passes a seed to every call, we draw from it so a run replays with the same seeds.
+    pub fn reseed(&mut self, seed: u64) {
+        self.visitor.get_rng().set_seed(seed);
+    }
+
+    /// our own inputs deserialize, everything else (eg. seeds) gets replaced by a generated one.
+    /// The same bytes always give the same input (AFL++ calibrates and replays crashes
+    /// through `afl_custom_post_process`), so we generate with a visitor seeded by their hash.
+    fn load(&mut self, buf: &[u8]) -> I {
+        match try_deserialize::<I>(buf) {
+            Some(input) => input,
+            None => {
+                let mut hasher = DefaultHasher::new();
+                buf.hash(&mut hasher);
+                let mut visitor = Visitor::new(hasher.finish(), DepthInfo::default());
+                let mut depth = visitor.generate_depth();
+                I::generate(&mut visitor, &mut depth, &mut 0)
+            }
+        }
+    }
+
+    fn fits(&mut self, input: &I) -> bool {
+        if !self.budget.fits(input) {
+            return false;
+        }
+        if self.budget.max_len.is_none() {
+            return true;
+        }
+        let len = self.converter.to_target_bytes(input).as_slice().len();
+        self.budget.fits_len(len)
+    }
+
+    fn learn(&mut self, input: &I) {
+        for (data, ty) in input.serialized().unwrap_or_default() {
+            let chunks = self.chunks.entry(ty).or_default();
+            if chunks.len() < MAX_CHUNKS {
+                chunks.push(data);
+            } else {
+                let index = self.visitor.random_range(0, MAX_CHUNKS - 1);
+                chunks[index] = data;
+            }
+        }
+    }
+
+    fn random_chunk(&mut self, ty: &Id) -> Option<Vec<u8>> {
+        let count = self.chunks.get(ty)?.len();
+        let index = self.visitor.random_range(0, count - 1);
+        self.chunks.get(ty)?.get(index).cloned()
+    }
+
+    /// ThesisSpliceMutator: replace a node (or an element of an iterable) with a chunk of the same type
+    fn splice(&mut self, input: &mut I) -> bool {
+        input.nodes(&mut self.visitor, 0);
+        let nodes = self.visitor.node_index();
+        if nodes.is_empty() {
+            return false;
+        }
+        let node = self.visitor.random_range(0, nodes.len() - 1);
+        let ((id, node_ty), ty) = nodes.get(node);
+        let mut path = nodes.path(node);
+        let ty = if let NodeType::Iterable(size, inner_ty) = node_ty {
+            path.push_back(self.visitor.random_range(0, *size));
+            inner_ty
+        } else {
+            ty
+        };
+        if let Some(data) = self.random_chunk(ty) {
+            input.__mutate(
+                &mut MutationType::Splice(&mut data.as_slice()),
+                &mut self.visitor,
+                path,
+            );
+            true
+        } else {
+            false
+        }
+    }
+
+    /// ThesisRecurseMutator: generate a node from scratch
+    fn recurse(&mut self, input: &mut I) -> bool {
+        input.nodes(&mut self.visitor, 0);
+        let nodes = self.visitor.node_index();
+        if nodes.is_empty() {
+            return false;
+        }
+        let path = nodes.path(self.visitor.random_range(0, nodes.len() - 1));
+        let bias = if self.visitor.coinflip() {
+            self.visitor.generate_depth()
+        } else {
+            0
+        };
+        input.__mutate(
+            &mut MutationType::GenerateReplace(bias),
+            &mut self.visitor,
+            path,
+        );
+        true
+    }
+
+    fn mutate(&mut self, input: &mut I) {
+        let stack = self.visitor.random_range(1, MAX_STACK);
+        for _ in 0..stack {
+            if !(self.visitor.coinflip() && self.splice(input)) {
+                self.recurse(input);
+            }
+        }
+    }
+
+    /// Mutate `buf`, splicing in chunks of `add_buf` (another queue entry) if we get one.
+    pub fn fuzz(&mut self, buf: &[u8], add_buf: Option<&[u8]>, max_size: usize) -> &[u8] {
+        let original = self.load(buf);
+        self.learn(&original);
+        if let Some(other) = add_buf.and_then(try_deserialize::<I>) {
+            self.learn(&other);
+        }
+        self.out.clear();
+        for _ in 0..MAX_TRIES {
+            let mut input = original.clone();
+            self.mutate(&mut input);
+            let serialized = serialize(&input);
+            if serialized.len() <= max_size && self.fits(&input) {
+                self.out = serialized;
+                break;
+            }
+        }
+        if self.out.is_empty() && buf.len() <= max_size {
+            // everything was too large, hand back what we got (cut short it would not deserialize)
+            self.out.extend_from_slice(buf);
+        }
+        &self.out
+    }
+
+    /// Splice chunks of `other` into `input`.
+    pub fn crossover(&mut self, input: &[u8], other: &[u8], max_size: usize) -> &[u8] {
+        let mut input = self.load(input);
+        if let Some(other) = try_deserialize::<I>(other) {
+            self.learn(&other);
+        }
+        self.splice(&mut input);
+        self.out = serialize(&input);
+        if self.out.len() > max_size || !self.fits(&input) {
+            self.out.truncate(0);
+        }
+        &self.out
+    }
+
+    /// Render a serialized input through the converter.
+    pub fn render(&mut self, buf: &[u8]) -> &[u8] {
+        let input = self.load(buf);
+        self.out = self
+            .converter
+            .to_target_bytes(&input)
+            .as_slice()
+            .to_vec();
+        &self.out
+    }
+}
+
+/// Export the AFL++ and libFuzzer custom mutator symbols for `$t`.
+/// `$c` is the converter type, eg. the one from `libafl_fuzzer::impl_converter!`
+#[macro_export]
+macro_rules! export_mutator {
+    ($t:ty, $c:ty) => {
+        type __ThesisMutator = $crate::GrammarMutator<$t, $c>;
+        type __ThesisThread = $crate::MutatorThread<__ThesisMutator>;
+
+        unsafe fn __thesis_slice<'a>(data: *const u8, size: usize) -> &'a [u8] {
+            if data.is_null() || size == 0 {
+                &[]
+            } else {
+                std::slice::from_raw_parts(data, size)
+            }
+        }
+
+        // AFL++
+
+        #[no_mangle]
+        pub extern "C" fn afl_custom_init(
+            _afl: *mut std::ffi::c_void,
+            seed: std::ffi::c_uint,
+        ) -> *mut std::ffi::c_void {
+            let seed = seed as u64;
+            let mutator = __ThesisThread::new(move || __ThesisMutator::new(seed, <$c>::new()));
+            Box::into_raw(Box::new(mutator)) as *mut std::ffi::c_void
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn afl_custom_fuzz(
+            data: *mut std::ffi::c_void,
+            buf: *mut u8,
+            buf_size: usize,
+            out_buf: *mut *const u8,
+            add_buf: *mut u8,
+            add_buf_size: usize,
+            max_size: usize,
+        ) -> usize {
+            let mutator = &*(data as *mut __ThesisThread);
+            mutator.run(|mutator| {
+                let add_buf = if add_buf.is_null() {
+                    None
+                } else {
+                    Some(__thesis_slice(add_buf, add_buf_size))
+                };
+                let out = mutator.fuzz(__thesis_slice(buf, buf_size), add_buf, max_size);
+                *out_buf = out.as_ptr();
+                out.len()
+            })
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn afl_custom_post_process(
+            data: *mut std::ffi::c_void,
+            buf: *mut u8,
+            buf_size: usize,
+            out_buf: *mut *const u8,
+        ) -> usize {
+            let mutator = &*(data as *mut __ThesisThread);
+            mutator.run(|mutator| {
+                let out = mutator.render(__thesis_slice(buf, buf_size));
+                *out_buf = out.as_ptr();
+                out.len()
+            })
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn afl_custom_deinit(data: *mut std::ffi::c_void) {
+            drop(Box::from_raw(data as *mut __ThesisThread));
+        }
+
+        // libFuzzer
+
+        thread_local! {
+            static __THESIS_MUTATOR: std::cell::OnceCell<__ThesisThread> =
+                std::cell::OnceCell::new();
+        }
+
+        /// runs `f` on the mutator thread, see `MutatorThread`.
+        /// `seed` is the one libFuzzer passed to this call, if it passed one.
+        fn __thesis_with<R>(seed: Option<u64>, f: impl FnOnce(&mut __ThesisMutator) -> R) -> R {
+            __THESIS_MUTATOR.with(|mutator| {
+                let first_seed = seed.unwrap_or(0);
+                mutator
+                    .get_or_init(|| {
+                        __ThesisThread::new(move || __ThesisMutator::new(first_seed, <$c>::new()))
+                    })
+                    .run(|mutator| {
+                        if let Some(seed) = seed {
+                            mutator.reseed(seed);
+                        }
+                        f(mutator)
+                    })
+            })
+        }
+
+        #[no_mangle]
+        #[allow(non_snake_case)]
+        pub unsafe extern "C" fn LLVMFuzzerCustomMutator(
+            data: *mut u8,
+            size: usize,
+            max_size: usize,
+            seed: std::ffi::c_uint,
+        ) -> usize {
+            __thesis_with(Some(seed as u64), |mutator| {
+                let out = mutator.fuzz(__thesis_slice(data, size), None, max_size);
+                std::ptr::copy_nonoverlapping(out.as_ptr(), data, out.len());
+                out.len()
+            })
+        }
+
+        #[no_mangle]
+        #[allow(non_snake_case)]
+        pub unsafe extern "C" fn LLVMFuzzerCustomCrossOver(
+            data1: *const u8,
+            size1: usize,
+            data2: *const u8,
+            size2: usize,
+            out: *mut u8,
+            max_out_size: usize,
+            seed: std::ffi::c_uint,
+        ) -> usize {
+            __thesis_with(Some(seed as u64), |mutator| {
+                let crossed = mutator.crossover(
+                    __thesis_slice(data1, size1),
+                    __thesis_slice(data2, size2),
+                    max_out_size,
+                );
+                std::ptr::copy_nonoverlapping(crossed.as_ptr(), out, crossed.len());
+                crossed.len()
+            })
+        }
+
+        /// For libFuzzer harnesses: render the serialized input libFuzzer hands to
+        /// LLVMFuzzerTestOneInput. The output is valid until the next call.
+        #[no_mangle]
+        pub unsafe extern "C" fn thesis_render(
+            data: *const u8,
+            size: usize,
+            out_buf: *mut *const u8,
+        ) -> usize {
+            __thesis_with(None, |mutator| {
+                let out = mutator.render(__thesis_slice(data, size));
+                *out_buf = out.as_ptr();
+                out.len()
+            })
+        }
+    };
+}
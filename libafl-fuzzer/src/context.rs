@@ -7,7 +7,7 @@ use std::{
     path::PathBuf,
     u128, time::Duration,
 };
-use thesis::{Node, Id};
+use thesis::{hash::Hash, Id, Node, NodeType};
 
 // Note: if we have no enums, then this is redundant cause everyone will have the same fields all
 // the time
@@ -18,19 +18,31 @@ pub struct Context {
     // types mapped to their inputs
     type_input_map: HashMap<Id, Vec<PathBuf>>,
 
+    // merkle hashes of the chunks we already stored
+    known_chunks: HashSet<[u8; 32]>,
+
     // path of nodes known to be observed in comparisons
     interesting_nodes: HashSet<Vec<usize>>,
     cmp_input_map: HashMap<u128, Vec<PathBuf>>,
 }
 // TODO: chunk & cmp reloading
 impl Context {
-    pub fn register_input<I>(&mut self, input: &I, corpus_id: CorpusId)
+    /// Store every subtree we have not seen yet as a chunk. Returns the input's hash.
+    pub fn register_input<I>(&mut self, input: &I, corpus_id: CorpusId) -> Hash
     where
         I: Node,
     {
 /*         let start = current_time(); */
-        for field in input.serialized().unwrap() {
-            let (data, ty) = field;
+        let root = thesis::hash::hash_tree(input, |hash, node, depth, data| {
+            // iterables are spliced element wise, the root is the testcase itself
+            if depth == 0 || matches!(node.node_type(), NodeType::Iterable(..)) {
+                return;
+            }
+            // if we know a subtree, we don't need to serialize it
+            if !self.known_chunks.insert(*hash.as_bytes()) {
+                return;
+            }
+            let ty = node.node_id();
             // todo: optimize this
             let path = self.out_dir.join("chunks").join(ty.to_string());
             match std::fs::create_dir(&path) {
@@ -43,7 +55,6 @@ impl Context {
                 }
             };
 
-            let hash = blake3::hash(&data);
             let path = path.join(hash.to_string());
             if !std::fs::exists(&path).unwrap() {
                 std::fs::write(&path, data).unwrap();
@@ -53,8 +64,9 @@ impl Context {
                     self.type_input_map.insert(ty, vec![path]);
                 }
             }
-        }
+        });
 /*         println!("ELAPSED={:?}", (current_time() - start).as_secs_f32()) */
+        root
     }
 
     pub fn get_inputs_for_type(&self, t: &Id) -> Option<&Vec<PathBuf>> {
@@ -110,6 +122,7 @@ impl Context {
             cmp_input_map,
             out_dir,
             type_input_map,
+            known_chunks: HashSet::default(),
             interesting_nodes,
         }
     }
@@ -0,0 +1,179 @@
+use std::{collections::HashSet, path::PathBuf};
+
+use libafl::Error;
+use thesis::Visitor;
+
+/// A `-x` argument: `path` or `path@level`.
+/// Like AFL, entries annotated with a higher `@level` than the file's are skipped.
+#[derive(Debug, Clone)]
+pub struct DictFile {
+    pub path: PathBuf,
+    pub level: usize,
+}
+
+impl DictFile {
+    pub fn from_cmdline(arg: &str) -> Result<Self, String> {
+        if let Some((path, level)) = arg.rsplit_once('@') {
+            if let Ok(level) = level.parse() {
+                return Ok(Self {
+                    path: PathBuf::from(path),
+                    level,
+                });
+            }
+        }
+        Ok(Self {
+            path: PathBuf::from(arg),
+            level: 0,
+        })
+    }
+
+    pub fn load(&self) -> Result<Vec<Vec<u8>>, Error> {
+        let contents = std::fs::read_to_string(&self.path)?;
+        parse(&contents, self.level)
+            .map_err(|e| Error::illegal_argument(format!("{}: {}", self.path.display(), e)))
+    }
+}
+
+/// Parse an AFL dictionary.
+/// Every non-empty, non-comment line is `"value"`, `name="value"` or `name@level="value"`.
+/// Values support `\\`, `\"` and `\xHH` escapes.
+pub fn parse(contents: &str, max_level: usize) -> Result<Vec<Vec<u8>>, String> {
+    let mut entries = vec![];
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let number = number + 1;
+        let (name, value) = match line.find('"') {
+            Some(start) => line.split_at(start),
+            None => return Err(format!("line {}: no value", number)),
+        };
+        let name = name.trim_end();
+        if !name.is_empty() {
+            let name = name
+                .strip_suffix('=')
+                .ok_or_else(|| format!("line {}: expected '=' before value", number))?
+                .trim_end();
+            if let Some((_, level)) = name.split_once('@') {
+                let level = level
+                    .parse::<usize>()
+                    .map_err(|_| format!("line {}: bad level '{}'", number, level))?;
+                if level > max_level {
+                    continue;
+                }
+            }
+        }
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .ok_or_else(|| format!("line {}: unterminated value", number))?;
+        entries.push(unescape(value).map_err(|e| format!("line {}: {}", number, e))?);
+    }
+    Ok(entries)
+}
+
+fn unescape(value: &str) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(value.len());
+    let mut bytes = value.bytes();
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'\\' => match bytes.next() {
+                Some(b'\\') => out.push(b'\\'),
+                Some(b'"') => out.push(b'"'),
+                Some(b'x') => {
+                    let hex = [
+                        bytes.next().ok_or("truncated \\x escape")?,
+                        bytes.next().ok_or("truncated \\x escape")?,
+                    ];
+                    let hex = std::str::from_utf8(&hex).map_err(|_| "bad \\x escape")?;
+                    out.push(u8::from_str_radix(hex, 16).map_err(|_| "bad \\x escape")?);
+                }
+                _ => return Err("bad escape".to_string()),
+            },
+            b'"' => return Err("unescaped '\"' in value".to_string()),
+            _ => out.push(byte),
+        }
+    }
+    Ok(out)
+}
+
+/// Load all dictionaries into the visitor's string pool.
+/// Our strings are `String`s, so non utf-8 (and empty) entries are dropped.
+pub fn register(dicts: &[DictFile], visitor: &mut Visitor) -> Result<usize, Error> {
+    let mut seen = HashSet::new();
+    for dict in dicts {
+        for entry in dict.load()? {
+            if let Ok(entry) = String::from_utf8(entry) {
+                if !entry.is_empty() && seen.insert(entry.clone()) {
+                    visitor.register_string(entry);
+                }
+            }
+        }
+    }
+    Ok(seen.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoting() {
+        let dict = "\"plain\"\nkw=\"with name\"\n  kw2 = \"spaced\"  \n\"say \\\"hi\\\"\"\n";
+        assert_eq!(
+            parse(dict, 0).unwrap(),
+            vec![
+                b"plain".to_vec(),
+                b"with name".to_vec(),
+                b"spaced".to_vec(),
+                b"say \"hi\"".to_vec(),
+            ]
+        );
+        assert!(parse("\"open", 0).is_err());
+        assert!(parse("no value", 0).is_err());
+        assert!(parse("kw \"missing equals\"", 0).is_err());
+        assert!(parse("\"a\"b\"", 0).is_err());
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(
+            parse("\"\\x00\\xff\\x41\"", 0).unwrap(),
+            vec![vec![0x00, 0xff, 0x41]]
+        );
+        assert_eq!(parse("\"a\\\\b\"", 0).unwrap(), vec![b"a\\b".to_vec()]);
+        assert!(parse("\"\\x4\"", 0).is_err());
+        assert!(parse("\"\\xzz\"", 0).is_err());
+        assert!(parse("\"\\n\"", 0).is_err());
+    }
+
+    #[test]
+    fn comments() {
+        let dict = "# a comment\n\n   # indented \"comment\"\n\"value\"\n";
+        assert_eq!(parse(dict, 0).unwrap(), vec![b"value".to_vec()]);
+    }
+
+    #[test]
+    fn levels() {
+        let dict = "a=\"zero\"\nb@1=\"one\"\nc@2=\"two\"\n";
+        assert_eq!(parse(dict, 0).unwrap(), vec![b"zero".to_vec()]);
+        assert_eq!(
+            parse(dict, 1).unwrap(),
+            vec![b"zero".to_vec(), b"one".to_vec()]
+        );
+        assert_eq!(parse(dict, 5).unwrap().len(), 3);
+        assert!(parse("a@x=\"bad\"", 0).is_err());
+    }
+
+    #[test]
+    fn cmdline() {
+        let dict = DictFile::from_cmdline("words.dict@2").unwrap();
+        assert_eq!((dict.path, dict.level), (PathBuf::from("words.dict"), 2));
+        let dict = DictFile::from_cmdline("mail@home.dict").unwrap();
+        assert_eq!(
+            (dict.path, dict.level),
+            (PathBuf::from("mail@home.dict"), 0)
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::{borrow::Cow, marker::PhantomData};
+use std::{borrow::Cow, cell::RefCell, marker::PhantomData, rc::Rc};
 
 use libafl::{
     corpus::{Corpus, Testcase},
@@ -9,17 +9,25 @@ use libafl::{
 };
 
 use libafl_bolts::Named;
-use thesis::Node;
+use thesis::{Node, Visitor};
 
-use crate::context::Context;
+use crate::{context::Context, index::TreeMetadata, stages::swarm::SwarmMetadata};
 
 pub struct RegisterFeedback<I> {
+    visitor: Rc<RefCell<Visitor>>,
+    /// reweight the visitor's generation choices every n registered inputs
+    /// None == we don't learn a probabilistic grammar
+    reweight_interval: Option<usize>,
+    registered: usize,
     phantom: PhantomData<I>,
 }
 
 impl<I> RegisterFeedback<I> {
-    pub fn new() -> Self {
+    pub fn new(visitor: Rc<RefCell<Visitor>>, reweight_interval: Option<usize>) -> Self {
         Self {
+            visitor,
+            reweight_interval,
+            registered: 0,
             phantom: PhantomData,
         }
     }
@@ -56,10 +64,24 @@ where
         let metadata = state
             .metadata_mut::<Context>()
             .expect("we must have context!");
-        metadata.register_input(
-            testcase.input().as_ref().expect("we must have input!"),
-            corpus_id,
-        );
+        if self.visitor.borrow().is_swarm() {
+            let swarm = SwarmMetadata::new(&self.visitor.borrow());
+            testcase.add_metadata(swarm);
+        }
+        let input = testcase.input().as_ref().expect("we must have input!");
+        let hash = metadata.register_input(input, corpus_id);
+        let tree = TreeMetadata::from_input(input, hash, &mut self.visitor.borrow_mut());
+        if let Some(interval) = self.reweight_interval {
+            let mut visitor = self.visitor.borrow_mut();
+            input.observe(&mut visitor);
+            self.registered += 1;
+            if self.registered % interval.max(1) == 0 {
+                visitor.reweight();
+            }
+        }
+        // we already hashed the tree, no need for the corpus to do it again in generate_name
+        *testcase.filename_mut() = Some(hash.to_string());
+        testcase.add_metadata(tree);
         Ok(())
     }
 }
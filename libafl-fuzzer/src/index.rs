@@ -0,0 +1,168 @@
+use std::{
+    cell::{Cell, Ref},
+    ops::Deref,
+};
+
+use libafl::{
+    corpus::{Corpus, CorpusId},
+    state::HasCorpus,
+    Error, HasMetadata, SerdeAny,
+};
+use serde::{Deserialize, Serialize};
+use thesis::{hash::Hash, Node, NodeIndex, Visitor};
+
+/// The node index and structural hash of a testcase's input, computed once per tree:
+/// mutators and stages don't walk it again, and the hash (also the testcase's file name)
+/// is there for whoever needs to tell inputs apart.
+/// Added by `RegisterFeedback` when the testcase enters the corpus, replaced by `set_input`.
+#[derive(Debug, Clone, SerdeAny, Serialize, Deserialize)]
+pub struct TreeMetadata {
+    /// not written with the testcase's metadata file, a testcase loaded from disk walks again
+    #[serde(skip)]
+    index: Option<NodeIndex>,
+    /// `thesis::hash::hash` of the input, `None` once `set_input` replaced it
+    pub hash: Option<[u8; 32]>,
+}
+
+impl TreeMetadata {
+    pub fn new(index: NodeIndex, hash: Option<Hash>) -> Self {
+        Self {
+            index: Some(index),
+            hash: hash.map(|hash| *hash.as_bytes()),
+        }
+    }
+
+    /// `hash` is the one `Context::register_input` got while storing the chunks
+    pub fn from_input<I: Node>(input: &I, hash: Hash, visitor: &mut Visitor) -> Self {
+        Self::new(walk(input, visitor), Some(hash))
+    }
+}
+
+/// Marks the start of a stack of mutations, set by `BudgetMutator`.
+/// The first mutator of the stack takes it: its input is still the testcase's.
+#[derive(Debug, Default, SerdeAny, Serialize, Deserialize)]
+pub struct StackStart {
+    #[serde(skip)]
+    unmutated: Cell<bool>,
+}
+
+/// `input` (a copy of the current testcase) goes through a stack of mutators next.
+pub fn start_stack<S: HasMetadata>(state: &mut S) {
+    if !state.has_metadata::<StackStart>() {
+        state.add_metadata(StackStart::default());
+    }
+    set_unmutated(state, true);
+}
+
+/// The stack is done, whether a mutator took the mark or not.
+pub fn end_stack<S: HasMetadata>(state: &mut S) {
+    set_unmutated(state, false);
+}
+
+fn set_unmutated<S: HasMetadata>(state: &S, unmutated: bool) {
+    if let Ok(start) = state.metadata::<StackStart>() {
+        start.unmutated.set(unmutated);
+    }
+}
+
+fn walk<I: Node>(input: &I, visitor: &mut Visitor) -> NodeIndex {
+    input.nodes(visitor, 0);
+    visitor.node_index()
+}
+
+/// Either the cached index or one we just built
+pub enum NodeIndexRef<'a> {
+    Cached(Ref<'a, NodeIndex>),
+    Walked(NodeIndex),
+}
+
+impl<'a> Deref for NodeIndexRef<'a> {
+    type Target = NodeIndex;
+
+    fn deref(&self) -> &NodeIndex {
+        match self {
+            NodeIndexRef::Cached(index) => index,
+            NodeIndexRef::Walked(index) => index,
+        }
+    }
+}
+
+impl<'a> NodeIndexRef<'a> {
+    pub fn into_owned(self) -> NodeIndex {
+        match self {
+            NodeIndexRef::Cached(index) => index.clone(),
+            NodeIndexRef::Walked(index) => index,
+        }
+    }
+}
+
+fn cached<S: HasCorpus>(state: &S, id: CorpusId) -> Option<Ref<'_, NodeIndex>> {
+    let testcase = state.corpus().get(id).ok()?.borrow();
+    Ref::filter_map(testcase, |testcase| {
+        testcase
+            .metadata::<TreeMetadata>()
+            .ok()
+            .and_then(|metadata| metadata.index.as_ref())
+    })
+    .ok()
+}
+
+/// The node index of `input`, an unmutated copy of the current testcase (eg. in a stage).
+pub fn current_node_index<'a, I, S>(
+    state: &'a S,
+    input: &I,
+    visitor: &mut Visitor,
+) -> NodeIndexRef<'a>
+where
+    I: Node,
+    S: HasCorpus,
+    S::Corpus: Corpus<Input = I>,
+{
+    match state
+        .corpus()
+        .current()
+        .and_then(|id| cached(state, id))
+    {
+        Some(index) => NodeIndexRef::Cached(index),
+        None => NodeIndexRef::Walked(walk(input, visitor)),
+    }
+}
+
+/// The node index of `input`, a copy of the current testcase that a mutator is about to change.
+/// Only the first mutator of a stack (see `start_stack`) gets the cached index, the rest walk `input`.
+pub fn mutator_node_index<'a, I, S>(
+    state: &'a S,
+    input: &I,
+    visitor: &mut Visitor,
+) -> NodeIndexRef<'a>
+where
+    I: Node,
+    S: HasCorpus + HasMetadata,
+    S::Corpus: Corpus<Input = I>,
+{
+    let unmutated = state
+        .metadata::<StackStart>()
+        .is_ok_and(|start| start.unmutated.replace(false));
+    if unmutated {
+        current_node_index(state, input, visitor)
+    } else {
+        NodeIndexRef::Walked(walk(input, visitor))
+    }
+}
+
+/// `Testcase::set_input` for the current testcase, the old index and hash go with the old input.
+/// We don't hash the new one: it is only named by its hash when it enters a corpus.
+pub fn set_input<I, S>(state: &mut S, input: I, index: NodeIndex) -> Result<(), Error>
+where
+    S: HasCorpus,
+    S::Corpus: Corpus<Input = I>,
+{
+    let id = state
+        .corpus()
+        .current()
+        .ok_or_else(|| Error::key_not_found("we must have a current testcase"))?;
+    let mut testcase = state.corpus().get(id)?.borrow_mut();
+    testcase.set_input(input);
+    testcase.add_metadata(TreeMetadata::new(index, None));
+    Ok(())
+}
@@ -1,13 +1,19 @@
 #![allow(warnings)]
 #![feature(core_intrinsics)]
 mod context;
+mod dict;
 mod feedback;
 mod hooks;
+mod index;
 mod mutators;
 mod scheduler;
+mod seeds;
 mod stages;
+mod tape;
+mod tools;
 use clap::Parser;
 use context::Context;
+use dict::DictFile;
 use feedback::register::RegisterFeedback;
 use libafl::{
     corpus::{CachedOnDiskCorpus, Corpus, OnDiskCorpus},
@@ -15,11 +21,12 @@ use libafl::{
     executors::ForkserverExecutor,
     feedback_or, feedback_or_fast,
     feedbacks::{
-        CrashFeedback, MaxMapOneOrFilledFeedback, MaxMapPow2Feedback, TimeFeedback, TimeoutFeedback,
+        CrashFeedback, MaxMapFeedback, MaxMapOneOrFilledFeedback, MaxMapPow2Feedback, TimeFeedback,
+        TimeoutFeedback,
     },
-    inputs::{Input, TargetBytesConverter},
+    inputs::{BytesInput, Input, TargetBytesConverter},
     monitors::{MultiMonitor, SimpleMonitor},
-    mutators::StdScheduledMutator,
+    mutators::{havoc_mutations, StdScheduledMutator},
     observers::{CanTrack, HitcountsMapObserver, StdMapObserver, TimeObserver},
     schedulers::{powersched::PowerSchedule, StdWeightedScheduler},
     stages::{IfStage, StdPowerMutationalStage},
@@ -38,8 +45,11 @@ use libafl_bolts::{
 };
 use libafl_targets::{AFLppCmpLogMap, AFLppCmpLogObserver};
 use mutators::{
-    recurse_mutate::ThesisRecurseMutator, splice::ThesisSpliceMutator,
-    splice_append::ThesisSpliceAppendMutator,
+    budget::BudgetMutator,
+    recurse_mutate::ThesisRecurseMutator,
+    splice::ThesisSpliceMutator,
+    splice_append::{self, ThesisSpliceAppendMutator},
+    tweak::ThesisTweakMutator,
 };
 
 #[cfg(feature = "scale")]
@@ -48,19 +58,52 @@ use parity_scale_codec::{Decode, Encode};
 use regex::Regex;
 use stages::{
     deterministic::DeterministicStage, generate::GenerateStage, minimization::MinimizationStage,
-    recursive_minimization::RecursiveMinimizationStage,
+    recursive_minimization::RecursiveMinimizationStage, swarm::SwarmStage,
 };
 use std::{cell::RefCell, io::ErrorKind, path::PathBuf, process::Command, rc::Rc, time::Duration};
-use thesis::{DepthInfo, Node, Visitor};
+use thesis::{budget::Budget, DepthInfo, Node, Visitor};
+
+pub use tape::TapeConverter;
 
 use crate::stages::generate::generate;
 
 const SHMEM_ENV_VAR: &str = "__AFL_SHM_ID";
+/// Our own traversals grow the stack as they go (`thesis::grow`), but (de)serializing recurses
+/// in serde, so deep inputs need more than the main thread's stack.
+/// The whole fuzzer runs on it, so it is mapped once at startup, not per execution.
+const FUZZER_STACK_SIZE: usize = 1 << 30;
+/// length of the random tapes we start with in tape mode (`-T`)
+const INITIAL_TAPE_LEN: usize = 256;
+
 pub fn fuzz<I, TC>(bytes_converter: TC)
 where
     I: Node + Input,
     TC: TargetBytesConverter<Input = I> + Clone,
 {
+    fuzz_with_setup(bytes_converter, |_| {});
+}
+
+/// `fuzz`, with a hook to set up the visitor before we start,
+/// eg. `visitor.register_mutate::<Identifier>()`
+pub fn fuzz_with_setup<I, TC, F>(bytes_converter: TC, setup: F)
+where
+    I: Node + Input,
+    TC: TargetBytesConverter<Input = I> + Clone,
+    F: FnOnce(&mut Visitor),
+{
+    thesis::with_stack(FUZZER_STACK_SIZE, || run_fuzzer(bytes_converter, setup));
+}
+
+fn run_fuzzer<I, TC, F>(bytes_converter: TC, setup: F)
+where
+    I: Node + Input,
+    TC: TargetBytesConverter<Input = I> + Clone,
+    F: FnOnce(&mut Visitor),
+{
+    if tools::is_tool() {
+        tools::run(bytes_converter);
+        return;
+    }
     let monitor = MultiMonitor::new(|s| println!("{s}"));
     /*     let monitor = MultiMonitor::new(|s| {}); */
     let mut mgr = SimpleEventManager::new(monitor);
@@ -100,14 +143,76 @@ where
     };
     let seed = opt.rng_seed.unwrap_or(current_nanos());
 
-    let mut visitor = Visitor::new(
-        seed,
-        DepthInfo {
-            expand: 1500,
-            generate: 3,
-            iterate: 5,
-        },
-    );
+    let depth = DepthInfo {
+        expand: 1500,
+        generate: 3,
+        iterate: 5,
+    };
+    let mut visitor = Visitor::new(seed, depth.clone());
+    if let Some(uniform_mix) = opt.pcfg_mix {
+        visitor.set_uniform_mix(uniform_mix);
+    }
+    if let Some(swarm_probability) = opt.swarm_probability {
+        visitor.set_swarm_probability(swarm_probability);
+    }
+    setup(&mut visitor);
+    if opt.tape {
+        // byte tapes mutated by havoc, `TapeConverter` decodes them into inputs for the target.
+        // None of the grammar mutators or stages, so this is a baseline for them.
+        println!("Fuzzing tapes, replay them with seed {seed}.");
+        let mut mgr = SimpleEventManager::new(MultiMonitor::new(|s| println!("{s}")));
+        let time_observer = TimeObserver::new("time");
+        let map_feedback = MaxMapFeedback::new(&edges_observer);
+        let mut feedback = feedback_or!(map_feedback, TimeFeedback::new(&time_observer));
+        let mut objective = feedback_or_fast!(CrashFeedback::new());
+        let mut state = StdState::new(
+            RomuDuoJrRand::with_seed(seed),
+            CachedOnDiskCorpus::<BytesInput>::new(fuzzer_dir.join("queue"), 2).unwrap(),
+            OnDiskCorpus::<BytesInput>::new(fuzzer_dir.join("crash")).unwrap(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let scheduler = StdWeightedScheduler::with_schedule(
+            &mut state,
+            &edges_observer,
+            Some(PowerSchedule::explore()),
+        );
+        let scheduler = scheduler.cycling_scheduler();
+        let mut executor = ForkserverExecutor::builder()
+            .program(opt.executable.clone())
+            .coverage_map_size(map_size)
+            .debug_child(opt.debug_child)
+            .is_persistent(true)
+            .is_deferred_frksrv(true)
+            .timeout(Duration::from_millis(opt.hang_timeout))
+            .shmem_provider(&mut shmem_provider)
+            .target_bytes_converter(TapeConverter::<I, TC>::new(
+                seed,
+                depth,
+                bytes_converter.clone(),
+            ))
+            .build(tuple_list!(edges_observer, time_observer))
+            .unwrap();
+        let mut fuzzer = StdFuzzer::new(scheduler, feedback, objective);
+        if state.must_load_initial_inputs() {
+            if opt.seeds_dir.is_some() {
+                println!("-i holds trees, not tapes: ignored.");
+            }
+            for _ in 0..opt.initial_generated_inputs {
+                let tape = BytesInput::new(visitor.generate_bytes(INITIAL_TAPE_LEN));
+                fuzzer
+                    .evaluate_input(&mut state, &mut executor, &mut mgr, tape)
+                    .unwrap();
+            }
+        }
+        let mutator = StdScheduledMutator::new(havoc_mutations());
+        let mut stages = tuple_list!(StdPowerMutationalStage::new(mutator));
+        fuzzer
+            .fuzz_loop(&mut stages, &mut executor, &mut state, &mut mgr)
+            .unwrap();
+        return;
+    }
     let visitor = Rc::new(RefCell::new(visitor));
     // Create a MapFeedback for coverage guided fuzzin'
     // We only care if an edge was hit, not how many times
@@ -121,7 +226,10 @@ where
     let mut feedback = feedback_or!(
         map_feedback,
         TimeFeedback::new(&time_observer),
-        RegisterFeedback::new()
+        RegisterFeedback::new(
+            Rc::clone(&visitor),
+            opt.pcfg_mix.map(|_| opt.pcfg_interval)
+        )
     );
 
     let mut objective = feedback_or_fast!(CrashFeedback::new());
@@ -165,12 +273,7 @@ where
 
     // Create our Fuzzer
     let mut fuzzer = StdFuzzer::new(scheduler, feedback, objective);
-    if let Some(dict_file) = &opt.dict_file {
-        let file = std::fs::read_to_string(dict_file).expect("cannot read dict file");
-        for entry in file.split("\n") {
-            visitor.borrow_mut().register_string(entry.to_string());
-        }
-    }
+    dict::register(&opt.dict_files, &mut visitor.borrow_mut()).expect("cannot load dict file");
     if opt.get_strings {
         let string_regex = Regex::new("^[a-zA-Z0-9_]+$").unwrap();
         let strings = Command::new("strings")
@@ -185,6 +288,16 @@ where
         }
     }
     if state.must_load_initial_inputs() {
+        if let Some(seeds_dir) = &opt.seeds_dir {
+            let seeds = seeds::load_dir::<I>(seeds_dir).expect("cannot load seeds");
+            println!("We loaded {} seeds from {}.", seeds.len(), seeds_dir.display());
+            // kept even without new coverage, someone wrote them for a reason
+            for seed in seeds {
+                fuzzer
+                    .add_input(&mut state, &mut executor, &mut mgr, seed)
+                    .unwrap();
+            }
+        }
         for _ in 0..opt.initial_generated_inputs {
             let generated: I = generate(&mut visitor.borrow_mut());
             fuzzer
@@ -194,18 +307,34 @@ where
         println!("We imported {} inputs from disk.", state.corpus().count());
     }
 
-    let mutator = StdScheduledMutator::with_max_stack_pow(
-        tuple_list!(
-            // SPLICE
-            ThesisSpliceMutator::new(Rc::clone(&visitor)),
-            ThesisSpliceMutator::new(Rc::clone(&visitor)),
-            // RECURSIVE GENERATE
-            ThesisRecurseMutator::new(Rc::clone(&visitor)),
-            ThesisRecurseMutator::new(Rc::clone(&visitor)),
-            // SPLICE APPEND
-            ThesisSpliceAppendMutator::new(Rc::clone(&visitor)),
+    let budget = Budget {
+        max_nodes: opt.max_nodes,
+        max_depth: opt.max_depth,
+        max_len: opt.max_len,
+    };
+    let mutator = BudgetMutator::new(
+        StdScheduledMutator::with_max_stack_pow(
+            tuple_list!(
+                // SPLICE
+                ThesisSpliceMutator::new(Rc::clone(&visitor)),
+                ThesisSpliceMutator::new(Rc::clone(&visitor)),
+                // RECURSIVE GENERATE
+                ThesisRecurseMutator::new(Rc::clone(&visitor)),
+                ThesisRecurseMutator::new(Rc::clone(&visitor)),
+                // SPLICE APPEND (capped unless the node budget keeps lists from growing forever)
+                ThesisSpliceAppendMutator::new(
+                    Rc::clone(&visitor),
+                    opt.max_nodes
+                        .is_none()
+                        .then_some(splice_append::UNBUDGETED_MAX_LEN),
+                ),
+                // TWEAK (skipped if no type implements thesis::Mutate)
+                ThesisTweakMutator::new(Rc::clone(&visitor)),
+            ),
+            3,
         ),
-        3,
+        budget,
+        bytes_converter.clone(),
     );
 
     let mut stages = tuple_list!(
@@ -213,6 +342,7 @@ where
         minimization_stage,
         recursive_minimization_stage,
         StdPowerMutationalStage::new(mutator),
+        SwarmStage::new(Rc::clone(&visitor), opt.swarm_epoch),
         /*         GenerateStage::new(Rc::clone(&visitor)), */
     );
 
@@ -249,17 +379,57 @@ struct Opt {
     #[arg(short = 'g', default_value_t = 100)]
     initial_generated_inputs: usize,
 
+    /// hand written seeds (`.json` / `.ron`, see the `convert` tool), loaded before we generate
+    #[arg(short = 'i')]
+    seeds_dir: Option<PathBuf>,
+
     #[arg(short = 'c', value_parser=Cores::from_cmdline)]
     cores: Cores,
 
-    #[arg(short = 'x')]
-    dict_file: Option<PathBuf>,
+    /// AFL style dictionary, may be given multiple times. `file@N` loads entries up to level N
+    #[arg(short = 'x', value_parser = DictFile::from_cmdline)]
+    dict_files: Vec<DictFile>,
 
     #[arg(short = 'e')]
     cmplog: bool,
 
     #[arg(short = 'S')]
     get_strings: bool,
+
+    /// learn variant weights from interesting inputs.
+    /// value is the mix against uniform (1.0 = always uniform, 0.0 = only learned)
+    #[arg(short = 'p')]
+    pcfg_mix: Option<f64>,
+
+    /// reweight the learned grammar every n new corpus entries
+    #[arg(short = 'P', default_value_t = 100)]
+    pcfg_interval: usize,
+
+    /// swarm testing: chance that a variant is switched off for an epoch
+    #[arg(short = 'w')]
+    swarm_probability: Option<f64>,
+
+    /// swarm epoch length in executions (a new configuration is drawn between testcases)
+    #[arg(short = 'W', default_value_t = 1000)]
+    swarm_epoch: usize,
+
+    /// mutations that give an input more nodes than this are thrown away.
+    /// without it, splice append leaves lists of more than 200 elements alone
+    #[arg(short = 'N')]
+    max_nodes: Option<usize>,
+
+    /// mutations that make an input deeper than this are thrown away
+    #[arg(short = 'D')]
+    max_depth: Option<usize>,
+
+    /// mutations that render to more bytes than this are thrown away
+    #[arg(short = 'L')]
+    max_len: Option<usize>,
+
+    /// fuzz byte tapes with havoc instead of trees, decoded with `TapeConverter`.
+    /// Tapes decode with the seed (`-s`) the run started with
+    #[arg(short = 'T')]
+    tape: bool,
 }
 
 #[macro_export]
@@ -348,10 +518,10 @@ macro_rules! impl_input {
                 std::fs::write(path, bytes)?;
                 Ok(())
             }
-            // TODO: don't serialize here
+            /// Only for testcases `RegisterFeedback` didn't name (eg. crashes),
+            /// corpus entries reuse the hash we computed when registering their chunks.
             fn generate_name(&self, id: Option<libafl::corpus::CorpusId>) -> String {
-                let bytes = thesis::serialize(self);
-                format!("{}", blake3::hash(bytes.as_slice()))
+                format!("{}", thesis::hash::hash(self))
             }
 
             fn from_file<P>(path: P) -> Result<Self, libafl::Error>
@@ -0,0 +1,72 @@
+use libafl::{
+    corpus::CorpusId,
+    inputs::TargetBytesConverter,
+    mutators::{MutationResult, Mutator},
+    HasMetadata,
+};
+use libafl_bolts::{AsSlice, Named};
+use std::borrow::Cow;
+use thesis::{budget::Budget, Node};
+
+use crate::index;
+
+/// Throws away mutations that take the input over the budget.
+/// Wraps the scheduled mutator, so the whole stack of mutations is checked at once
+/// and no input over the budget is ever executed or added to the corpus.
+/// We don't trim: a stack over the budget is thrown away whole and the input stays as it was.
+pub struct BudgetMutator<M, TC> {
+    inner: M,
+    budget: Budget,
+    converter: TC,
+}
+
+impl<M, TC> BudgetMutator<M, TC> {
+    pub fn new(inner: M, budget: Budget, converter: TC) -> Self {
+        Self {
+            inner,
+            budget,
+            converter,
+        }
+    }
+}
+
+impl<I, S, M, TC> Mutator<I, S> for BudgetMutator<M, TC>
+where
+    I: Node,
+    S: HasMetadata,
+    M: Mutator<I, S>,
+    TC: TargetBytesConverter<Input = I>,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, libafl::Error> {
+        index::start_stack(state);
+        let result = self.inner.mutate(state, input);
+        index::end_stack(state);
+        if result? == MutationResult::Skipped {
+            return Ok(MutationResult::Skipped);
+        }
+        if !self.budget.fits(input) {
+            return Ok(MutationResult::Skipped);
+        }
+        if self.budget.max_len.is_some() {
+            let rendered = self.converter.to_target_bytes(input);
+            if !self.budget.fits_len(rendered.as_slice().len()) {
+                return Ok(MutationResult::Skipped);
+            }
+        }
+        Ok(MutationResult::Mutated)
+    }
+
+    fn post_exec(
+        &mut self,
+        state: &mut S,
+        new_corpus_id: Option<CorpusId>,
+    ) -> Result<(), libafl::Error> {
+        self.inner.post_exec(state, new_corpus_id)
+    }
+}
+
+impl<M, TC> Named for BudgetMutator<M, TC> {
+    fn name(&self) -> &std::borrow::Cow<'static, str> {
+        &Cow::Borrowed("BudgetMutator")
+    }
+}
@@ -1,3 +1,5 @@
+pub mod budget;
 pub mod recurse_mutate;
 pub mod splice;
 pub mod splice_append;
+pub mod tweak;
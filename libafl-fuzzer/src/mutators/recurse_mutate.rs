@@ -9,7 +9,7 @@ use std::{borrow::Cow, cell::RefCell, collections::VecDeque, marker::PhantomData
 use thesis::Visitor;
 use thesis::{MutationType, Node};
 
-use crate::context::Context;
+use crate::{context::Context, index};
 
 pub struct ThesisRecurseMutator<I> {
     visitor: Rc<RefCell<Visitor>>,
@@ -23,18 +23,17 @@ where
     S::Corpus: Corpus<Input = I>,
 {
     fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, libafl::Error> {
-        input.nodes(&mut self.visitor.borrow_mut(), 0);
-        let mut nodes = self.visitor.borrow_mut().nodes();
+        let nodes = index::mutator_node_index(state, input, &mut self.visitor.borrow_mut());
         let field_splice_index = self.visitor.borrow_mut().random_range(0, nodes.len() - 1);
-        let field = &mut nodes[field_splice_index];
-        let ((id, node_ty), ty) = field.last().unwrap();
+        let field = nodes.get(field_splice_index);
+        let ((id, node_ty), ty) = field;
         let mut bias = if self.visitor.borrow_mut().coinflip() { self.visitor.borrow().generate_depth() } else {0};
         if matches!(node_ty, thesis::NodeType::Iterable(_, _)) {
-                let field_len = field.last().unwrap().0 .1.iterable_size();
+                let field_len = node_ty.iterable_size();
                 if field_len < 3 {
                     return Ok(MutationResult::Skipped);
                 }
-                let mut path = VecDeque::from_iter(field.iter().map(|(i, ty)| i.0));
+                let mut path = nodes.path(field_splice_index);
                 let subslice_start = self.visitor.borrow_mut().random_range(0, field_len - 1);
                 let mut subslice_end = self
                     .visitor
@@ -44,7 +43,7 @@ where
                     subslice_end = subslice_start + 5;
                 }
                 for index in subslice_start..subslice_end {
-                    let mut path = VecDeque::from_iter(field.iter().map(|(i, ty)| i.0));
+                    let mut path = nodes.path(field_splice_index);
                     path.push_back(index);
                     #[cfg(debug_assertions)]
                     println!("recursive_mutate | subslice | {:?}", field);
@@ -55,7 +54,7 @@ where
                     );
                 }
         } else {
-            let mut path = VecDeque::from_iter(field.iter().map(|(i, ty)| i.0));
+            let mut path = nodes.path(field_splice_index);
             #[cfg(debug_assertions)]
             println!("recursive_mutate | single | {:?}", field);
             input.__mutate(
@@ -9,7 +9,7 @@ use std::{borrow::Cow, cell::RefCell, collections::VecDeque, marker::PhantomData
 use thesis::Visitor;
 use thesis::{MutationType, Node};
 
-use crate::context::Context;
+use crate::{context::Context, index};
 
 pub struct ThesisSpliceMutator<I> {
     visitor: Rc<RefCell<Visitor>>,
@@ -24,22 +24,21 @@ where
 {
     fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, libafl::Error> {
         let metadata = state.metadata::<Context>().unwrap();
-        input.nodes(&mut self.visitor.borrow_mut(), 0);
-        let mut nodes = self.visitor.borrow_mut().nodes();
+        let nodes = index::mutator_node_index(state, input, &mut self.visitor.borrow_mut());
         let field_splice_index = self.visitor.borrow_mut().random_range(0, nodes.len() - 1);
-        let field = &nodes[field_splice_index];
-        let ((id, node_ty), ty) = field.last().unwrap();
+        let field = nodes.get(field_splice_index);
+        let ((id, node_ty), ty) = field;
         if matches!(node_ty, thesis::NodeType::Iterable(_, _)) {
             let inner_ty = node_ty.inner_id();
             let subslice = self.visitor.borrow_mut().coinflip_with_prob(0.9);
             if subslice {
                 // no point subslicing when we have less than 5 entries
-                let field_len = field.last().unwrap().0 .1.iterable_size();
+                let field_len = node_ty.iterable_size();
                 if field_len < 3 {
                     return Ok(MutationResult::Skipped);
                 }
                 if let Some(possible_splices) = metadata.get_inputs_for_type(&inner_ty) {
-                    let mut path = VecDeque::from_iter(field.iter().map(|(i, ty)| i.0));
+                    let mut path = nodes.path(field_splice_index);
                     let subslice_start = self.visitor.borrow_mut().random_range(0, field_len - 1);
                     let mut subslice_end = self
                         .visitor
@@ -79,7 +78,7 @@ where
                     // unfortunately we need to replace the exact amount.
                     // cause we don't differentiate between recursive vec and slice
                     let max_iter_size = node_ty.iterable_size();
-                    let path = VecDeque::from_iter(field.iter().map(|(i, ty)| i.0));
+                    let path = nodes.path(field_splice_index);
                     let items = (0..max_iter_size)
                         .into_iter()
                         .map(|_| {
@@ -111,7 +110,7 @@ where
             }
         } else {
             if let Some(possible_splices) = metadata.get_inputs_for_type(ty) {
-                let mut path = VecDeque::from_iter(field.iter().map(|(i, ty)| i.0));
+                let mut path = nodes.path(field_splice_index);
                 let random_splice = possible_splices
                     .get(
                         self.visitor
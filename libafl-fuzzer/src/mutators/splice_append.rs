@@ -9,10 +9,15 @@ use std::{borrow::Cow, cell::RefCell, collections::VecDeque, marker::PhantomData
 use thesis::Node;
 use thesis::Visitor;
 
-use crate::context::Context;
+use crate::{context::Context, index};
+
+/// Iterables at least this long are not appended to, unless a node budget (`-N`) limits growth
+pub const UNBUDGETED_MAX_LEN: usize = 200;
 
 pub struct ThesisSpliceAppendMutator<I> {
     visitor: Rc<RefCell<Visitor>>,
+    /// skip iterables with more elements than this
+    max_len: Option<usize>,
     phantom: PhantomData<I>,
 }
 
@@ -24,18 +29,17 @@ where
 {
     fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, libafl::Error> {
         let metadata = state.metadata::<Context>().unwrap();
-        input.nodes(&mut self.visitor.borrow_mut(), 0);
-        let mut nodes = self.visitor.borrow_mut().nodes();
+        let nodes = index::mutator_node_index(state, input, &mut self.visitor.borrow_mut());
         let field_splice_index = self.visitor.borrow_mut().random_range(0, nodes.len() - 1);
-        let field = &nodes[field_splice_index];
-        let ((id, node_ty), ty) = field.last().unwrap();
+        let field = nodes.get(field_splice_index);
+        let ((id, node_ty), ty) = field;
         if let thesis::NodeType::Iterable(field_len, inner_ty) = node_ty {
             if let Some(possible_splices) = metadata.get_inputs_for_type(&inner_ty) {
-                if *field_len > 200 {
+                if self.max_len.is_some_and(|max_len| *field_len > max_len) {
                     return Ok(MutationResult::Skipped);
                 }
                 // calculate subsplice size
-                let path = VecDeque::from_iter(field.iter().map(|(i, ty)| i.0));
+                let path = nodes.path(field_splice_index);
                 let random_splice = possible_splices
                     .get(
                         self.visitor
@@ -74,9 +78,11 @@ impl<I> Named for ThesisSpliceAppendMutator<I> {
     }
 }
 impl<I> ThesisSpliceAppendMutator<I> {
-    pub fn new(visitor: Rc<RefCell<Visitor>>) -> Self {
+    /// `max_len`: iterables longer than this are left alone, see `UNBUDGETED_MAX_LEN`
+    pub fn new(visitor: Rc<RefCell<Visitor>>, max_len: Option<usize>) -> Self {
         Self {
             visitor,
+            max_len,
             phantom: PhantomData,
         }
     }
@@ -0,0 +1,75 @@
+use libafl::{
+    corpus::Corpus,
+    mutators::{MutationResult, Mutator},
+    state::{HasCorpus, HasRand, State},
+    HasMetadata,
+};
+use libafl_bolts::Named;
+use std::{borrow::Cow, cell::RefCell, marker::PhantomData, rc::Rc};
+use thesis::{MutationType, Node, Visitor};
+
+use crate::index;
+
+/// Tweak a node whose type has a `thesis::Mutate` impl (see `Visitor::register_mutate`) in place
+pub struct ThesisTweakMutator<I> {
+    visitor: Rc<RefCell<Visitor>>,
+    phantom: PhantomData<I>,
+}
+
+impl<I, S> Mutator<I, S> for ThesisTweakMutator<I>
+where
+    I: Node,
+    S: State + HasCorpus + HasRand + HasMetadata,
+    S::Corpus: Corpus<Input = I>,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, libafl::Error> {
+        if !self.visitor.borrow().has_tweaks() {
+            return Ok(MutationResult::Skipped);
+        }
+        let nodes = index::mutator_node_index(state, input, &mut self.visitor.borrow_mut());
+        let candidates = {
+            let visitor = self.visitor.borrow();
+            (0..nodes.len())
+                .filter(|node| visitor.can_tweak(&nodes.get(*node).1))
+                .collect::<Vec<_>>()
+        };
+        if candidates.is_empty() {
+            return Ok(MutationResult::Skipped);
+        }
+        let node = candidates[self
+            .visitor
+            .borrow_mut()
+            .random_range(0, candidates.len() - 1)];
+        #[cfg(debug_assertions)]
+        println!("tweak | {:?}", nodes.get(node));
+        let mut tweak = MutationType::Tweak(false);
+        input.__mutate(&mut tweak, &mut self.visitor.borrow_mut(), nodes.path(node));
+        match tweak {
+            MutationType::Tweak(true) => Ok(MutationResult::Mutated),
+            _ => Ok(MutationResult::Skipped),
+        }
+    }
+
+    fn post_exec(
+        &mut self,
+        _state: &mut S,
+        _new_corpus_id: Option<libafl::corpus::CorpusId>,
+    ) -> Result<(), libafl::Error> {
+        Ok(())
+    }
+}
+
+impl<I> Named for ThesisTweakMutator<I> {
+    fn name(&self) -> &std::borrow::Cow<'static, str> {
+        &Cow::Borrowed("ThesisTweakMutator")
+    }
+}
+
+impl<I> ThesisTweakMutator<I> {
+    pub fn new(visitor: Rc<RefCell<Visitor>>) -> Self {
+        Self {
+            visitor,
+            phantom: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! Inputs as text, so seeds can be written and edited by hand.
+//! The corpus stays bincode, JSON and RON are only read (`-i`) and written (`convert`) by us.
+use std::path::{Path, PathBuf};
+
+use libafl::Error;
+use serde::Deserialize;
+use thesis::Node;
+
+/// How an input is stored, from the file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// what the corpus holds
+    Bincode,
+    Json,
+    Ron,
+}
+
+impl Format {
+    /// `.json` and `.ron`, anything else is bincode (corpus files have no extension)
+    pub fn of(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => Self::Json,
+            Some("ron") => Self::Ron,
+            _ => Self::Bincode,
+        }
+    }
+
+    /// Trees nest deeper than the recursion limits of serde_json and ron,
+    /// so we turn them off and rely on the fuzzer's stack (`thesis::with_stack`) instead.
+    pub fn parse<I: Node>(&self, data: &[u8]) -> Result<I, String> {
+        match self {
+            Self::Bincode => {
+                thesis::try_deserialize(data).ok_or_else(|| "not a serialized input".to_string())
+            }
+            Self::Json => {
+                let mut deserializer = serde_json::Deserializer::from_slice(data);
+                deserializer.disable_recursion_limit();
+                let input = I::deserialize(&mut deserializer).map_err(|e| e.to_string())?;
+                deserializer.end().map_err(|e| e.to_string())?;
+                Ok(input)
+            }
+            Self::Ron => {
+                let text = std::str::from_utf8(data).map_err(|e| e.to_string())?;
+                ron::Options::default()
+                    .without_recursion_limit()
+                    .from_str(text)
+                    .map_err(|e| e.to_string())
+            }
+        }
+    }
+
+    /// JSON can't hold NaN or infinite floats and only has string map keys, use RON for those.
+    pub fn print<I: Node>(&self, input: &I) -> Result<Vec<u8>, String> {
+        match self {
+            Self::Bincode => Ok(thesis::serialize(input)),
+            Self::Json => serde_json::to_vec_pretty(input).map_err(|e| e.to_string()),
+            Self::Ron => ron::Options::default()
+                .without_recursion_limit()
+                .to_string_pretty(input, ron::ser::PrettyConfig::default())
+                .map(String::into_bytes)
+                .map_err(|e| e.to_string()),
+        }
+    }
+}
+
+pub fn read<I: Node>(path: &Path) -> Result<I, Error> {
+    let data = std::fs::read(path)?;
+    Format::of(path)
+        .parse(&data)
+        .map_err(|e| Error::illegal_argument(format!("{}: {}", path.display(), e)))
+}
+
+pub fn write<I: Node>(input: &I, path: &Path) -> Result<(), Error> {
+    let data = Format::of(path)
+        .print(input)
+        .map_err(|e| Error::illegal_argument(format!("{}: {}", path.display(), e)))?;
+    std::fs::write(path, data)?;
+    Ok(())
+}
+
+/// The `.json` and `.ron` seeds of a `-i` directory, in file name order.
+/// A seed that doesn't parse is an error: it was written for this grammar, so it's a typo.
+pub fn load_dir<I: Node>(dir: &Path) -> Result<Vec<I>, Error> {
+    let mut paths = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<PathBuf>, _>>()?;
+    paths.retain(|path| path.is_file() && Format::of(path) != Format::Bincode);
+    paths.sort();
+    paths.iter().map(|path| read(path)).collect()
+}
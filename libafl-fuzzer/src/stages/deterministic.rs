@@ -15,6 +15,8 @@ use std::{
 };
 use thesis::{Node, Visitor};
 
+use crate::index;
+
 #[derive(Debug)]
 pub struct DeterministicStage<E, S, I> {
     visitor: Rc<RefCell<Visitor>>,
@@ -57,12 +59,12 @@ where
             return Ok(());
         }
         let input = state.current_input_cloned()?;
-        input.nodes(&mut self.visitor.borrow_mut(), 0);
-        let mut nodes = self.visitor.borrow_mut().nodes();
+        let nodes =
+            index::current_node_index(state, &input, &mut self.visitor.borrow_mut()).into_owned();
         // generate replace every field
-        for field in nodes {
+        for node in 0..nodes.len() {
             let mut unmutated_input = state.current_input_cloned()?;
-            let mut path = VecDeque::from_iter(field.iter().map(|(i, ty)| i.0));
+            let mut path = nodes.path(node);
             unmutated_input.__mutate(
                 &mut thesis::MutationType::GenerateReplace(3),
                 &mut self.visitor.borrow_mut(), 
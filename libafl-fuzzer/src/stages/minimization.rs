@@ -21,7 +21,7 @@ use std::{
 };
 use thesis::{MutationType, Node, NodeType, Visitor};
 
-use crate::context::Context;
+use crate::{context::Context, index};
 
 #[derive(Debug)]
 pub struct MinimizationStage<C, E, O, OT, S, I> {
@@ -95,18 +95,18 @@ where
             .list
             .clone();
         let mut current = state.current_input_cloned().unwrap();
-        current.nodes(&mut self.visitor.borrow_mut(), 0);
         let mut skip = 0;
-        let mut nodes = self.visitor.borrow_mut().nodes();
+        let mut nodes =
+            index::current_node_index(state, &current, &mut self.visitor.borrow_mut()).into_owned();
+        let mut remaining = nodes.len();
         
         loop {
-            let field = nodes.pop();
-            if field.is_none() {break;}
-            let field = field.unwrap();
-            let ((id, node_ty), ty) = field.last().unwrap();
+            if remaining == 0 {break;}
+            remaining -= 1;
+            let ((id, node_ty), ty) = nodes.get(remaining).clone();
             if let NodeType::Iterable(field_len, inner_ty) = node_ty {
-                let path = VecDeque::from_iter(field.iter().map(|(i, ty)| i.0));
-                let mut len = *field_len;
+                let path = nodes.path(remaining);
+                let mut len = field_len;
                 let mut counter = 0;
                 loop {
                     if len == 0 || counter >= len {
@@ -135,14 +135,15 @@ where
 /*                         println!("MINIMIZED"); */
                         current = inner;
                         current.nodes(&mut self.visitor.borrow_mut(), 0);
-                        nodes = self.visitor.borrow_mut().nodes();
+                        nodes = self.visitor.borrow_mut().node_index();
+                        remaining = nodes.len();
                         len = len.saturating_sub(1);
                     }
                     counter += 1;
                 }
             }
         }
-        state.current_testcase_mut()?.set_input(current);
+        index::set_input(state, current, nodes)?;
         Ok(())
     }
 
@@ -3,3 +3,4 @@ pub mod deterministic;
 pub mod generate;
 pub mod recursive_minimization;
 pub mod minimization;
+pub mod swarm;
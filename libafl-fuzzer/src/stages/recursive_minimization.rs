@@ -21,7 +21,7 @@ use std::{
 };
 use thesis::{MutationType, Node, NodeType, Visitor};
 
-use crate::context::Context;
+use crate::{context::Context, index};
 
 #[derive(Debug)]
 pub struct RecursiveMinimizationStage<C, E, O, OT, S, I> {
@@ -95,16 +95,16 @@ where
             .list
             .clone();
         let mut current = state.current_input_cloned().unwrap();
-        current.nodes(&mut self.visitor.borrow_mut(), 0);
         let mut skip = 0;
-        let mut nodes = self.visitor.borrow_mut().nodes();
+        let mut nodes =
+            index::current_node_index(state, &current, &mut self.visitor.borrow_mut()).into_owned();
+        let mut remaining = nodes.len();
         loop {
-            let field = nodes.pop();
-            if field.is_none() {break;}
-            let field = field.unwrap();
-            let ((id, node_ty), ty) = field.last().unwrap();
+            if remaining == 0 {break;}
+            remaining -= 1;
+            let ((id, node_ty), ty) = nodes.get(remaining);
             if let NodeType::Recursive = node_ty {
-                let path = VecDeque::from_iter(field.iter().map(|(i, ty)| i.0));
+                let path = nodes.path(remaining);
                     let mut inner = current.clone();
                     inner.__mutate(
                         &mut MutationType::RecursiveReplace,
@@ -128,11 +128,12 @@ where
                         println!("RECURSIVE_MINIMIZED");
                         current = inner;
                         current.nodes(&mut self.visitor.borrow_mut(), 0);
-                        nodes = self.visitor.borrow_mut().nodes();
+                        nodes = self.visitor.borrow_mut().node_index();
+                        remaining = nodes.len();
                     }
             }
         }
-        state.current_testcase_mut()?.set_input(current);
+        index::set_input(state, current, nodes)?;
         Ok(())
     }
 
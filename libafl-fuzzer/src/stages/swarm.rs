@@ -0,0 +1,94 @@
+use libafl::{
+    corpus::Corpus,
+    executors::Executor,
+    inputs::UsesInput,
+    stages::Stage,
+    state::{HasCorpus, HasCurrentTestcase, HasExecutions, State, UsesState},
+    Evaluator, SerdeAny,
+};
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, marker::PhantomData, rc::Rc};
+use thesis::{Id, Node, Visitor};
+
+/// The swarm configuration a testcase was found with, attached to it by `RegisterFeedback`
+/// when it enters the corpus. The stage itself keeps nothing: the epoch lives in the visitor.
+#[derive(Debug, Clone, SerdeAny, Serialize, Deserialize)]
+pub struct SwarmMetadata {
+    pub epoch: usize,
+    /// (type, variant) pairs switched off when the testcase was registered. Variants are drawn
+    /// the first time we generate their type, so every choice that built the input is in here;
+    /// types drawn later in the epoch are not. `Option` and `Result` are never switched off.
+    pub disabled: Vec<(Id, usize)>,
+}
+
+impl SwarmMetadata {
+    pub fn new(visitor: &Visitor) -> Self {
+        Self {
+            epoch: visitor.swarm_epoch(),
+            disabled: visitor.swarm_disabled(),
+        }
+    }
+}
+
+/// Draws a new swarm configuration every `epoch_len` executions.
+/// Stages run once per scheduled testcase, so we check between testcases:
+/// an epoch lasts at least as long as the mutational stage of one testcase.
+#[derive(Debug)]
+pub struct SwarmStage<E, S, I> {
+    visitor: Rc<RefCell<Visitor>>,
+    epoch_len: u64,
+    /// executions when the current epoch started
+    epoch_start: u64,
+    phantom: PhantomData<(E, S, I)>,
+}
+
+impl<E, S, I> SwarmStage<E, S, I> {
+    pub fn new(visitor: Rc<RefCell<Visitor>>, epoch_len: usize) -> Self {
+        Self {
+            visitor,
+            epoch_len: epoch_len.max(1) as u64,
+            epoch_start: 0,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<E, S, I> UsesState for SwarmStage<E, S, I>
+where
+    S: State,
+{
+    type State = S;
+}
+
+impl<E, EM, Z, S, I> Stage<E, EM, Z> for SwarmStage<E, S, I>
+where
+    I: Node + Serialize,
+    S: State + HasCurrentTestcase + HasCorpus + HasExecutions + UsesInput<Input = I>,
+    S::Corpus: Corpus<Input = I>,
+    E: UsesState<State = S> + Executor<E, EM, State = S>,
+    EM: UsesState<State = S>,
+    Z: UsesState<State = S> + Evaluator<E, EM>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut Self::State,
+        manager: &mut EM,
+    ) -> Result<(), libafl_bolts::Error> {
+        let executions = *state.executions();
+        if executions - self.epoch_start >= self.epoch_len {
+            self.epoch_start = executions;
+            self.visitor.borrow_mut().new_swarm_epoch();
+        }
+        Ok(())
+    }
+
+    fn should_restart(&mut self, state: &mut Self::State) -> Result<bool, libafl_bolts::Error> {
+        Ok(true)
+    }
+
+    fn clear_progress(&mut self, state: &mut Self::State) -> Result<(), libafl_bolts::Error> {
+        Ok(())
+    }
+}
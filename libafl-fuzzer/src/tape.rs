@@ -0,0 +1,44 @@
+use libafl::inputs::{BytesInput, HasTargetBytes, TargetBytesConverter};
+use libafl_bolts::{ownedref::OwnedSlice, AsSlice};
+use std::{cell::RefCell, marker::PhantomData, rc::Rc};
+use thesis::{DepthInfo, Node, Visitor};
+
+/// Runs a grammar target from a plain `BytesInput`.
+/// The bytes are a decision tape for the visitor, so byte-level mutators (havoc etc.)
+/// mutate the choices while every input stays valid for the grammar.
+///
+/// We decode with a visitor of our own, not the fuzzer's: its weights, swarm and string pool
+/// change as we fuzz, and a corpus entry or crash must decode the same way every time.
+#[derive(Clone)]
+pub struct TapeConverter<I, TC> {
+    visitor: Rc<RefCell<Visitor>>,
+    inner: TC,
+    phantom: PhantomData<I>,
+}
+
+impl<I, TC> TapeConverter<I, TC> {
+    /// `inner` renders the decoded input, eg. the one from `impl_converter!`.
+    /// `seed` picks the string pool, replay a tape with the same seed and `depth`.
+    pub fn new(seed: u64, depth: DepthInfo, inner: TC) -> Self {
+        Self {
+            visitor: Rc::new(RefCell::new(Visitor::new(seed, depth))),
+            inner,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<I, TC> TargetBytesConverter for TapeConverter<I, TC>
+where
+    I: Node,
+    TC: TargetBytesConverter<Input = I>,
+{
+    type Input = BytesInput;
+
+    fn to_target_bytes<'a>(&mut self, input: &'a Self::Input) -> OwnedSlice<'a, u8> {
+        let tape = input.target_bytes().as_slice().to_vec();
+        let decoded: I = thesis::generate_from_tape(&mut self.visitor.borrow_mut(), tape);
+        let rendered = self.inner.to_target_bytes(&decoded).as_slice().to_vec();
+        OwnedSlice::from(rendered)
+    }
+}
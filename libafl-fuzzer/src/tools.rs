@@ -0,0 +1,106 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+use libafl::inputs::TargetBytesConverter;
+use libafl_bolts::AsSlice;
+use thesis::Node;
+
+use crate::seeds;
+
+/// Triage helpers, run instead of fuzzing when the first argument is one of the subcommands.
+/// eg. `unparser-js dump queue/<id>`
+#[derive(Debug, Parser, Clone)]
+#[command(name = "thesis")]
+struct ToolOpt {
+    #[command(subcommand)]
+    tool: Tool,
+}
+
+#[derive(Debug, Subcommand, Clone)]
+enum Tool {
+    /// Print the tree of an input, one node per line
+    Dump {
+        input: PathBuf,
+        /// max length of the rendered snippets
+        #[arg(short = 'n', default_value_t = 80)]
+        max_snippet: usize,
+    },
+    /// Print the subtrees that were inserted, removed or replaced between two inputs
+    Diff {
+        old: PathBuf,
+        new: PathBuf,
+        /// max length of the printed node values
+        #[arg(short = 'n', default_value_t = 80)]
+        max_snippet: usize,
+    },
+    /// Check the grammar for recursion without an end, unreachable variants and id collisions
+    Lint,
+    /// Print Rust source that builds an input, to paste a crash into a regression test
+    Rust { input: PathBuf },
+    /// Convert an input between bincode, JSON (`.json`) and RON (`.ron`), by file extension.
+    /// If `output` is a directory (eg. `queue`), the input is written there as a corpus file.
+    Convert { input: PathBuf, output: PathBuf },
+}
+
+const TOOLS: &[&str] = &["dump", "diff", "lint", "rust", "convert"];
+
+pub fn is_tool() -> bool {
+    std::env::args()
+        .nth(1)
+        .is_some_and(|arg| TOOLS.contains(&arg.as_str()))
+}
+
+fn load<I: Node>(path: &PathBuf) -> I {
+    seeds::read(path).unwrap_or_else(|e| panic!("cannot load input: {}", e))
+}
+
+pub fn run<I, TC>(mut bytes_converter: TC)
+where
+    I: Node,
+    TC: TargetBytesConverter<Input = I>,
+{
+    match ToolOpt::parse().tool {
+        Tool::Dump { input, max_snippet } => {
+            let input = load::<I>(&input);
+            // we can only render the root, the rest of the tree shows its values
+            let dump = thesis::walk::dump(&input, max_snippet, |node| {
+                let node = node.downcast_ref::<I>()?;
+                let rendered = bytes_converter.to_target_bytes(node);
+                Some(String::from_utf8_lossy(rendered.as_slice()).to_string())
+            });
+            print!("{}", dump);
+        }
+        Tool::Diff {
+            old,
+            new,
+            max_snippet,
+        } => {
+            let changes = thesis::diff::diff(&load::<I>(&old), &load::<I>(&new));
+            for change in &changes {
+                println!("{}", change.format(max_snippet));
+            }
+            println!("{} changes", changes.len());
+        }
+        Tool::Lint => {
+            let lints = thesis::lint::lint::<I>();
+            for lint in &lints {
+                println!("{}", lint);
+            }
+            println!("{} lints", lints.len());
+        }
+        Tool::Rust { input } => {
+            println!("{}", load::<I>(&input).to_rust_expr());
+        }
+        Tool::Convert { input, output } => {
+            let input = load::<I>(&input);
+            // named like the fuzzer names its corpus files
+            let output = if output.is_dir() {
+                output.join(thesis::hash::hash(&input).to_string())
+            } else {
+                output
+            };
+            seeds::write(&input, &output).expect("cannot write output");
+            println!("{}", output.display());
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use crate::{
+    walk::{walk, DynNode, Walk},
+    Node,
+};
+
+/// Limits on the size of an input, so mutations don't grow the corpus into timeouts.
+/// `None` == no limit
+#[derive(Debug, Clone, Default)]
+pub struct Budget {
+    /// nodes in the tree, counted like `walk` visits them
+    pub max_nodes: Option<usize>,
+    pub max_depth: Option<usize>,
+    /// length of the rendered input
+    pub max_len: Option<usize>,
+}
+
+/// How many nodes a tree has and how deep it is
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TreeSize {
+    pub nodes: usize,
+    pub depth: usize,
+}
+
+#[derive(Default)]
+struct Measure {
+    size: TreeSize,
+    depth: usize,
+}
+
+impl Walk for Measure {
+    fn enter(&mut self, index: usize, node: &dyn DynNode) {
+        self.depth += 1;
+        self.size.nodes += 1;
+        self.size.depth = self.size.depth.max(self.depth);
+    }
+
+    fn leave(&mut self, node: &dyn DynNode) {
+        self.depth -= 1;
+    }
+}
+
+pub fn tree_size<T: Node>(root: &T) -> TreeSize {
+    let mut measure = Measure::default();
+    walk(root, &mut measure);
+    measure.size
+}
+
+impl Budget {
+    /// Is `input` within the node and depth limits.
+    /// Only walks the tree if one of them is set.
+    pub fn fits<T: Node>(&self, input: &T) -> bool {
+        if self.max_nodes.is_none() && self.max_depth.is_none() {
+            return true;
+        }
+        let size = tree_size(input);
+        self.max_nodes.map_or(true, |max| size.nodes <= max)
+            && self.max_depth.map_or(true, |max| size.depth <= max)
+    }
+
+    /// Is a rendered input of `len` bytes within the limit
+    pub fn fits_len(&self, len: usize) -> bool {
+        self.max_len.map_or(true, |max| len <= max)
+    }
+}
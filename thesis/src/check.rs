@@ -0,0 +1,259 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    panic::{self, AssertUnwindSafe},
+};
+
+use libafl_bolts::rands::Rand;
+
+use crate::{
+    chunks::Offsets,
+    serialize, try_deserialize,
+    walk::{walk, DynNode, Walk},
+    DepthInfo, MutationType, Node, NodeType, Visitor,
+};
+
+/// The first thing `check` found broken
+#[derive(Debug, Clone)]
+pub struct Failure {
+    /// `Visitor::new(seed, DepthInfo::default())` (and `setup`) generates the input again
+    pub seed: u64,
+    /// where we mutated, None if the input itself is broken
+    pub path: Option<VecDeque<usize>>,
+    /// the visitor's rng was seeded with this right before the mutation:
+    /// `visitor.get_rng().set_seed(step_seed)` after generating the input, then `__mutate`
+    pub step_seed: Option<u64>,
+    /// eg. `generate`, `round trip`, `Splice`
+    pub step: String,
+    pub message: String,
+    /// Debug output of the input before the step
+    pub input: String,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "seed {}: {} failed", self.seed, self.step)?;
+        if let Some(path) = &self.path {
+            write!(f, " at {:?}", path)?;
+        }
+        if let Some(step_seed) = self.step_seed {
+            write!(f, " (step seed {})", step_seed)?;
+        }
+        write!(f, ": {}\n{}", self.message, self.input)
+    }
+}
+
+/// The serialized bytes of every node below the root, by `__mutate` path
+struct Chunks<'a> {
+    offsets: Offsets,
+    bytes: &'a [u8],
+    path: Vec<usize>,
+    chunks: HashMap<Vec<usize>, &'a [u8]>,
+}
+
+impl<'a> Walk for Chunks<'a> {
+    fn enter(&mut self, index: usize, node: &dyn DynNode) {
+        if self.offsets.depth() > 0 {
+            self.path.push(index);
+        }
+        self.offsets.enter(node);
+    }
+
+    fn leave(&mut self, node: &dyn DynNode) {
+        let range = self.offsets.leave(node);
+        if self.offsets.depth() > 0 {
+            self.chunks.insert(self.path.clone(), &self.bytes[range]);
+            self.path.pop();
+        }
+    }
+
+    fn variant(&mut self, index: usize, name: &'static str) {
+        self.path.push(index);
+    }
+
+    fn leave_variant(&mut self) {
+        self.path.pop();
+    }
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "panicked".to_string()
+    }
+}
+
+struct Checker<T> {
+    seed: u64,
+    /// what the rng of the current mutation was seeded with
+    step_seed: Option<u64>,
+    visitor: Visitor,
+    phantom: std::marker::PhantomData<T>,
+}
+
+impl<T> Checker<T>
+where
+    T: Node + Clone,
+{
+    fn fail(&self, step: &str, path: Option<&VecDeque<usize>>, input: &T, message: String) -> Failure {
+        Failure {
+            seed: self.seed,
+            path: path.cloned(),
+            step_seed: self.step_seed,
+            step: step.to_string(),
+            message,
+            input: format!("{:?}", input),
+        }
+    }
+
+    /// `input` must come back the same from its encoding
+    fn round_trip(&self, step: &str, path: Option<&VecDeque<usize>>, input: &T) -> Result<Vec<u8>, Failure> {
+        let bytes = panic::catch_unwind(AssertUnwindSafe(|| serialize(input)))
+            .map_err(|payload| self.fail(step, path, input, panic_message(payload)))?;
+        match try_deserialize::<T>(&bytes) {
+            Some(back) if serialize(&back) == bytes => Ok(bytes),
+            Some(_) => Err(self.fail(step, path, input, "round trip changed the input".to_string())),
+            None => Err(self.fail(step, path, input, "does not deserialize".to_string())),
+        }
+    }
+
+    fn mutate(
+        &mut self,
+        step: &str,
+        input: &T,
+        path: &VecDeque<usize>,
+        ty: &mut MutationType,
+    ) -> Result<T, Failure> {
+        let mut mutated = input.clone();
+        // the rng has moved on since we generated, so every mutation gets a seed of its own
+        let step_seed = self.visitor.get_rng().next();
+        self.visitor.get_rng().set_seed(step_seed);
+        self.step_seed = Some(step_seed);
+        let visitor = &mut self.visitor;
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            mutated.__mutate(ty, visitor, path.clone());
+        }))
+        .map_err(|payload| self.fail(step, Some(path), input, panic_message(payload)))?;
+        self.round_trip(step, Some(path), &mutated)?;
+        Ok(mutated)
+    }
+
+    fn check(&mut self) -> Result<(), Failure> {
+        let mut depth = self.visitor.generate_depth();
+        let visitor = &mut self.visitor;
+        let input = panic::catch_unwind(AssertUnwindSafe(|| T::generate(visitor, &mut depth, &mut 0)))
+            .map_err(|payload| Failure {
+                seed: self.seed,
+                path: None,
+                step_seed: None,
+                step: "generate".to_string(),
+                message: panic_message(payload),
+                input: String::new(),
+            })?;
+        let bytes = self.round_trip("round trip", None, &input)?;
+
+        let mut chunks = Chunks {
+            offsets: Offsets::default(),
+            bytes: &bytes,
+            path: vec![],
+            chunks: HashMap::default(),
+        };
+        walk(&input, &mut chunks);
+        let chunks = chunks.chunks;
+
+        input.nodes(&mut self.visitor, 0);
+        let nodes = self.visitor.node_index();
+        for node in 0..nodes.len() {
+            self.step_seed = None;
+            let path = nodes.path(node);
+            let ((_, node_ty), id) = nodes.get(node);
+            let Some(chunk) = chunks.get(&Vec::from(path.clone())) else {
+                return Err(self.fail(
+                    "nodes",
+                    Some(&path),
+                    &input,
+                    "nodes() reports a path the tree does not have".to_string(),
+                ));
+            };
+
+            // splicing a node into its own place must not change anything
+            let mut data: &[u8] = chunk;
+            let spliced = self.mutate("Splice", &input, &path, &mut MutationType::Splice(&mut data))?;
+            if serialize(&spliced) != bytes {
+                return Err(self.fail(
+                    "Splice",
+                    Some(&path),
+                    &input,
+                    "splicing a chunk into its own path changed the input".to_string(),
+                ));
+            }
+
+            let bias = self.visitor.generate_depth();
+            self.mutate("GenerateReplace", &input, &path, &mut MutationType::GenerateReplace(bias))?;
+
+            if self.visitor.can_tweak(id) {
+                self.mutate("Tweak", &input, &path, &mut MutationType::Tweak(false))?;
+            }
+
+            match node_ty {
+                NodeType::Iterable(size, _) => {
+                    self.mutate("IterablePop", &input, &path, &mut MutationType::IterablePop(*size))?;
+                    let mut element_path = Vec::from(path.clone());
+                    element_path.push(0);
+                    if let Some(element) = chunks.get(&element_path) {
+                        let mut data: &[u8] = element;
+                        self.mutate("SpliceAppend", &input, &path, &mut MutationType::SpliceAppend(&mut data))?;
+                    }
+                }
+                NodeType::Recursive => {
+                    self.mutate("RecursiveReplace", &input, &path, &mut MutationType::RecursiveReplace)?;
+                }
+                NodeType::NonRecursive => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Self test of a grammar: generate `instances` inputs of `T` (seeds 0..instances) and check that
+/// - they survive a serialize / deserialize round trip
+/// - every path `nodes()` reports exists, and splicing the node's own chunk there is a no-op
+/// - every mutation the fuzzer would make at that path works and round trips
+///   (`Tweak` only where `setup` registered a `Mutate` type, see `check_with_setup`)
+///
+/// Returns the first failure, with the seed, path and step seed to reproduce it.
+/// Panics are caught with `catch_unwind`, the panic hook is left alone,
+/// so the ones we turn into failures are still printed to stderr.
+///
+/// ```ignore
+/// thesis::check::<Code>(1000).unwrap_or_else(|failure| panic!("{}", failure));
+/// ```
+pub fn check<T>(instances: u64) -> Result<(), Failure>
+where
+    T: Node + Clone,
+{
+    check_with_setup::<T, _>(instances, |_| {})
+}
+
+/// `check`, with a hook to set up each visitor like the fuzzer's,
+/// eg. `visitor.register_mutate::<Identifier>()` so `Tweak` is checked too
+pub fn check_with_setup<T, F>(instances: u64, setup: F) -> Result<(), Failure>
+where
+    T: Node + Clone,
+    F: Fn(&mut Visitor),
+{
+    (0..instances).try_for_each(|seed| {
+        let mut visitor = Visitor::new(seed, DepthInfo::default());
+        setup(&mut visitor);
+        Checker::<T> {
+            seed,
+            step_seed: None,
+            visitor,
+            phantom: std::marker::PhantomData,
+        }
+        .check()
+    })
+}
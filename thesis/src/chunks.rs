@@ -0,0 +1,81 @@
+use std::ops::Range;
+
+use crate::{
+    serialize,
+    walk::{walk, DynNode, Walk},
+    Id, Node, NodeType,
+};
+
+/// Tracks where each node's encoding starts and ends in the encoding of the root,
+/// so we serialize a tree once and slice every subtree out of it.
+///
+/// bincode writes a node as its header (enum tag, length, ...) followed by its children,
+/// leaves are measured with `serialized_size`.
+#[derive(Debug, Default)]
+pub struct Offsets {
+    cursor: usize,
+    /// (start, has children) of the nodes we are in
+    stack: Vec<(usize, bool)>,
+}
+
+impl Offsets {
+    pub fn enter(&mut self, node: &dyn DynNode) {
+        if let Some(parent) = self.stack.last_mut() {
+            parent.1 = true;
+        }
+        self.stack.push((self.cursor, false));
+        self.cursor += node.header_len();
+    }
+
+    /// The range of `node` in the root's encoding
+    pub fn leave(&mut self, node: &dyn DynNode) -> Range<usize> {
+        let (start, has_children) = self.stack.pop().expect("T0bq8Yd2____");
+        if !has_children {
+            self.cursor = start + node.size();
+        }
+        start..self.cursor
+    }
+
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+}
+
+/// Calls `on_chunk` with every subtree we splice with: everything but the root and iterables
+/// (those are spliced element wise).
+pub struct Chunker<'a, F> {
+    pub offsets: Offsets,
+    pub bytes: &'a [u8],
+    pub on_chunk: F,
+}
+
+impl<'a, F> Walk for Chunker<'a, F>
+where
+    F: FnMut(&'a [u8], &dyn DynNode),
+{
+    fn enter(&mut self, index: usize, node: &dyn DynNode) {
+        self.offsets.enter(node);
+    }
+
+    fn leave(&mut self, node: &dyn DynNode) {
+        let range = self.offsets.leave(node);
+        if self.offsets.depth() > 0 && !matches!(node.node_type(), NodeType::Iterable(..)) {
+            (self.on_chunk)(&self.bytes[range], node);
+        }
+    }
+}
+
+/// The serialized chunks of `root` with their type, see `Node::serialized`.
+pub fn chunks<T: Node>(root: &T) -> Vec<(Vec<u8>, Id)> {
+    let bytes = serialize(root);
+    let mut chunks = vec![];
+    walk(
+        root,
+        &mut Chunker {
+            offsets: Offsets::default(),
+            bytes: &bytes,
+            on_chunk: |data: &[u8], node: &dyn DynNode| chunks.push((data.to_vec(), node.node_id())),
+        },
+    );
+    chunks
+}
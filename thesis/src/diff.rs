@@ -0,0 +1,244 @@
+use crate::{
+    hash::{self, Hash},
+    walk::{short_type_name, walk, DynNode, Walk},
+    Node,
+};
+
+/// An owned copy of a tree, with the structural hash of every node.
+#[derive(Debug, Clone)]
+pub struct DiffNode {
+    /// the `__mutate` path
+    pub path: Vec<usize>,
+    pub type_name: &'static str,
+    pub variant: Option<(usize, &'static str)>,
+    pub iterable: bool,
+    pub hash: Hash,
+    pub debug: String,
+    pub children: Vec<DiffNode>,
+}
+
+impl DiffNode {
+    pub fn from_node<T: Node>(root: &T) -> Self {
+        let mut builder = Builder {
+            path: vec![],
+            stack: vec![],
+            root: None,
+        };
+        walk(root, &mut builder);
+        builder.root.expect("wK0rCk4N____")
+    }
+
+    fn name(&self) -> String {
+        match self.variant {
+            Some((_, variant)) => format!("{}::{}", short_type_name(self.type_name), variant),
+            None => short_type_name(self.type_name),
+        }
+    }
+}
+
+struct Builder {
+    path: Vec<usize>,
+    stack: Vec<DiffNode>,
+    root: Option<DiffNode>,
+}
+
+impl Walk for Builder {
+    fn enter(&mut self, index: usize, node: &dyn DynNode) {
+        if !self.stack.is_empty() {
+            self.path.push(index);
+        }
+        self.stack.push(DiffNode {
+            path: self.path.clone(),
+            type_name: node.type_name(),
+            variant: None,
+            iterable: matches!(node.node_type(), crate::NodeType::Iterable(..)),
+            // filled in once we know our children
+            hash: Hash::from([0; 32]),
+            debug: node.debug(),
+            children: vec![],
+        });
+    }
+
+    fn leave(&mut self, node: &dyn DynNode) {
+        let mut diff_node = self.stack.pop().expect("lX8MvJ1e____");
+        let variant = diff_node.variant.map(|v| v.0);
+        diff_node.hash = if diff_node.children.is_empty() {
+            hash::leaf(diff_node.type_name, variant, &node.bytes())
+        } else {
+            hash::inner(
+                diff_node.type_name,
+                variant,
+                diff_node
+                    .children
+                    .iter()
+                    .map(|child| (*child.path.last().expect("Lq5Wd0rM____"), &child.hash)),
+            )
+        };
+        let node = diff_node;
+        if let Some(parent) = self.stack.last_mut() {
+            parent.children.push(node);
+            self.path.pop();
+        } else {
+            self.root = Some(node);
+        }
+    }
+
+    fn variant(&mut self, index: usize, name: &'static str) {
+        self.path.push(index);
+        if let Some(node) = self.stack.last_mut() {
+            node.variant = Some((index, name));
+        }
+    }
+
+    fn leave_variant(&mut self) {
+        self.path.pop();
+    }
+}
+
+/// A changed subtree. Paths of removed nodes are in the old tree, inserted ones in the new tree.
+#[derive(Debug, Clone)]
+pub enum Change {
+    Inserted(DiffNode),
+    Removed(DiffNode),
+    Replaced(DiffNode, DiffNode),
+}
+
+fn fmt_path(path: &[usize]) -> String {
+    path.iter()
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+impl Change {
+    /// one line per change, node values are cut after `max_snippet` characters
+    pub fn format(&self, max_snippet: usize) -> String {
+        let snippet = |node: &DiffNode| {
+            let debug = node.debug.escape_debug().to_string();
+            if debug.chars().count() > max_snippet {
+                format!("{}...", debug.chars().take(max_snippet).collect::<String>())
+            } else {
+                debug
+            }
+        };
+        match self {
+            Change::Inserted(node) => {
+                format!("+ [{}] {} | {}", fmt_path(&node.path), node.name(), snippet(node))
+            }
+            Change::Removed(node) => {
+                format!("- [{}] {} | {}", fmt_path(&node.path), node.name(), snippet(node))
+            }
+            Change::Replaced(old, new) => format!(
+                "~ [{}] {} -> [{}] {} | {} -> {}",
+                fmt_path(&old.path),
+                old.name(),
+                fmt_path(&new.path),
+                new.name(),
+                snippet(old),
+                snippet(new)
+            ),
+        }
+    }
+}
+
+/// Structural diff of two inputs.
+/// Subtrees with the same structural hash are equal, iterables are aligned on their elements
+/// so an insert in the middle of a `Vec` is one `Inserted`, not a replace of every element after it.
+pub fn diff<T: Node>(old: &T, new: &T) -> Vec<Change> {
+    let mut changes = vec![];
+    diff_nodes(
+        DiffNode::from_node(old),
+        DiffNode::from_node(new),
+        &mut changes,
+    );
+    changes
+}
+
+pub fn diff_nodes(old: DiffNode, new: DiffNode, changes: &mut Vec<Change>) {
+    crate::grow(|| diff_nodes_inner(old, new, changes))
+}
+
+fn diff_nodes_inner(old: DiffNode, new: DiffNode, changes: &mut Vec<Change>) {
+    if old.hash == new.hash {
+        return;
+    }
+    if old.type_name != new.type_name
+        || old.variant.map(|v| v.0) != new.variant.map(|v| v.0)
+        || old.children.is_empty() && new.children.is_empty()
+    {
+        changes.push(Change::Replaced(old, new));
+        return;
+    }
+    let same_shape = !old.iterable
+        && !new.iterable
+        && old.children.len() == new.children.len()
+        && old
+            .children
+            .iter()
+            .zip(new.children.iter())
+            .all(|(a, b)| a.path.last() == b.path.last());
+    if same_shape {
+        for (old, new) in old.children.into_iter().zip(new.children.into_iter()) {
+            diff_nodes(old, new, changes);
+        }
+    } else {
+        diff_children(old.children, new.children, changes);
+    }
+}
+
+/// Align children on the longest common subsequence of their hashes.
+/// Between two aligned pairs, the leftovers are paired up and diffed, the rest is inserted / removed.
+fn diff_children(old: Vec<DiffNode>, new: Vec<DiffNode>, changes: &mut Vec<Change>) {
+    let (n, m) = (old.len(), new.len());
+    let equal = |a: &DiffNode, b: &DiffNode| a.hash == b.hash;
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if equal(&old[i], &new[j]) {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut old = old.into_iter().map(Some).collect::<Vec<_>>();
+    let mut new = new.into_iter().map(Some).collect::<Vec<_>>();
+    let (mut i, mut j) = (0, 0);
+    let (mut removed, mut inserted) = (vec![], vec![]);
+    while i < n || j < m {
+        // equal elements are always on a longest common subsequence
+        let matched = i < n
+            && j < m
+            && equal(
+                old[i].as_ref().expect("R0f7hXNs____"),
+                new[j].as_ref().expect("Pz1kQm4e____"),
+            );
+        if matched {
+            flush(&mut removed, &mut inserted, changes);
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            inserted.push(new[j].take().expect("XK9tT0Gc____"));
+            j += 1;
+        } else {
+            removed.push(old[i].take().expect("Q2vmnV7R____"));
+            i += 1;
+        }
+    }
+    flush(&mut removed, &mut inserted, changes);
+}
+
+fn flush(removed: &mut Vec<DiffNode>, inserted: &mut Vec<DiffNode>, changes: &mut Vec<Change>) {
+    let paired = removed.len().min(inserted.len());
+    let mut removed = removed.drain(..);
+    let mut inserted = inserted.drain(..);
+    for _ in 0..paired {
+        diff_nodes(
+            removed.next().expect("f1Jr9q2S____"),
+            inserted.next().expect("Zf3oY0dd____"),
+            changes,
+        );
+    }
+    changes.extend(removed.map(Change::Removed));
+    changes.extend(inserted.map(Change::Inserted));
+}
@@ -0,0 +1,114 @@
+pub use blake3::Hash;
+
+use crate::{
+    chunks::Offsets,
+    serialize,
+    walk::{walk, DynNode, Walk},
+    Node,
+};
+
+/// Hash of a leaf: its type and its serialized bytes.
+pub fn leaf(type_name: &str, variant: Option<usize>, bytes: &[u8]) -> Hash {
+    let mut hasher = start(type_name, variant);
+    hasher.update(&[0xff]);
+    hasher.update(bytes);
+    hasher.finalize()
+}
+
+/// Hash of an inner node: its type, variant and the (path segment, hash) of its children.
+/// Two subtrees of the same type hash the same iff they serialize the same.
+pub fn inner<'a>(
+    type_name: &str,
+    variant: Option<usize>,
+    children: impl Iterator<Item = (usize, &'a Hash)>,
+) -> Hash {
+    let mut hasher = start(type_name, variant);
+    for (index, hash) in children {
+        hasher.update(&(index as u64).to_le_bytes());
+        hasher.update(hash.as_bytes());
+    }
+    hasher.finalize()
+}
+
+fn start(type_name: &str, variant: Option<usize>) -> blake3::Hasher {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(type_name.as_bytes());
+    hasher.update(&(variant.map(|v| v as u64).unwrap_or(u64::MAX)).to_le_bytes());
+    hasher
+}
+
+/// Merkle hashes every subtree, bottom up, on top of one serialization of the root.
+/// `on_node` gets every node with its subtree hash, depth (0 == root) and serialized bytes
+/// once its children are done.
+pub struct MerkleWalk<'a, F> {
+    /// (path segment, variant, children) of the nodes we are in
+    stack: Vec<(usize, Option<usize>, Vec<(usize, Hash)>)>,
+    offsets: Offsets,
+    bytes: &'a [u8],
+    on_node: F,
+}
+
+impl<'a, F> Walk for MerkleWalk<'a, F>
+where
+    F: FnMut(&Hash, &dyn DynNode, usize, &'a [u8]),
+{
+    fn enter(&mut self, index: usize, node: &dyn DynNode) {
+        self.offsets.enter(node);
+        self.stack.push((index, None, vec![]));
+    }
+
+    fn leave(&mut self, node: &dyn DynNode) {
+        let (index, variant, children) = self.stack.pop().expect("Vb3Tn9xQ____");
+        let data = &self.bytes[self.offsets.leave(node)];
+        let hash = if children.is_empty() {
+            leaf(node.type_name(), variant, data)
+        } else {
+            inner(
+                node.type_name(),
+                variant,
+                children.iter().map(|(index, hash)| (*index, hash)),
+            )
+        };
+        (self.on_node)(&hash, node, self.stack.len(), data);
+        if let Some(parent) = self.stack.last_mut() {
+            parent.2.push((index, hash));
+        }
+    }
+
+    fn variant(&mut self, index: usize, name: &'static str) {
+        if let Some(node) = self.stack.last_mut() {
+            node.1 = Some(index);
+        }
+    }
+}
+
+/// Hash every subtree of `root`, returns the root hash.
+pub fn hash_tree<T, F>(root: &T, on_node: F) -> Hash
+where
+    T: Node,
+    F: FnMut(&Hash, &dyn DynNode, usize, &[u8]),
+{
+    let bytes = serialize(root);
+    let mut root_hash = None;
+    let mut on_node = on_node;
+    walk(
+        root,
+        &mut MerkleWalk {
+            stack: vec![],
+            offsets: Offsets::default(),
+            bytes: &bytes,
+            on_node: |hash: &Hash, node: &dyn DynNode, depth: usize, data: &[u8]| {
+                if depth == 0 {
+                    root_hash = Some(*hash);
+                }
+                on_node(hash, node, depth, data);
+            },
+        },
+    );
+    root_hash.expect("u8HcR2wD____")
+}
+
+/// The structural hash of `root`.
+pub fn hash<T: Node>(root: &T) -> Hash {
+    hash_tree(root, |_, _, _, _| {})
+}
@@ -0,0 +1,87 @@
+use std::collections::VecDeque;
+
+use crate::{Id, NodeType};
+
+const NO_PARENT: u32 = u32::MAX;
+
+/// The nodes of a tree, as registered by `Node::nodes`.
+///
+/// Every field we step into (including the ones that are only path segments, eg. `Option`)
+/// is stored once, with a pointer to its parent, so registering a node is a push instead of a
+/// copy of the whole field stack. Paths are rebuilt on demand.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "bincode", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodeIndex {
+    /// (parent entry, field) of every field we stepped into
+    entries: Vec<(u32, ((usize, NodeType), Id))>,
+    /// the entries that are nodes, in registration order
+    nodes: Vec<u32>,
+}
+
+impl NodeIndex {
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// The innermost field of node `index`, ie. what `field.last()` was for `Visitor::nodes`
+    pub fn get(&self, index: usize) -> &((usize, NodeType), Id) {
+        &self.entries[self.nodes[index] as usize].1
+    }
+
+    /// The `__mutate` path of node `index`
+    pub fn path(&self, index: usize) -> VecDeque<usize> {
+        self.entry_path(self.nodes[index])
+    }
+
+    /// The whole field stack of node `index`, root first
+    pub fn fields(&self, index: usize) -> Vec<((usize, NodeType), Id)> {
+        self.entry_fields(self.nodes[index])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &((usize, NodeType), Id)> {
+        self.nodes
+            .iter()
+            .map(|entry| &self.entries[*entry as usize].1)
+    }
+
+    pub(crate) fn push(&mut self, parent: Option<u32>, field: ((usize, NodeType), Id)) -> u32 {
+        let entry = u32::try_from(self.entries.len()).expect(crate::ERR_OVERFLOW);
+        self.entries.push((parent.unwrap_or(NO_PARENT), field));
+        entry
+    }
+
+    pub(crate) fn mark_node(&mut self, entry: u32) {
+        self.nodes.push(entry);
+    }
+
+    pub(crate) fn parent(&self, entry: u32) -> Option<u32> {
+        let parent = self.entries[entry as usize].0;
+        (parent != NO_PARENT).then_some(parent)
+    }
+
+    pub(crate) fn entry_path(&self, entry: u32) -> VecDeque<usize> {
+        let mut path = VecDeque::new();
+        for entry in self.ancestors(entry) {
+            path.push_front(self.entries[entry as usize].1 .0 .0);
+        }
+        path
+    }
+
+    pub(crate) fn entry_fields(&self, entry: u32) -> Vec<((usize, NodeType), Id)> {
+        let mut fields = self
+            .ancestors(entry)
+            .map(|entry| self.entries[entry as usize].1.clone())
+            .collect::<Vec<_>>();
+        fields.reverse();
+        fields
+    }
+
+    /// `entry` and its parents, innermost first
+    fn ancestors(&self, entry: u32) -> impl Iterator<Item = u32> + '_ {
+        std::iter::successors(Some(entry), |entry| self.parent(*entry))
+    }
+}
@@ -1,18 +1,53 @@
 #![allow(warnings)]
 #![feature(core_intrinsics)]
 
+#[cfg(feature = "bincode")]
+pub mod budget;
+#[cfg(feature = "bincode")]
+pub mod check;
+#[cfg(feature = "bincode")]
+pub mod chunks;
+#[cfg(feature = "bincode")]
+pub mod diff;
+#[cfg(feature = "bincode")]
+pub mod hash;
+pub mod index;
+#[cfg(feature = "bincode")]
+pub mod lint;
+pub mod mutate;
+pub mod remote;
+pub mod render;
 #[cfg(feature = "bincode")]
 pub mod serde;
+#[cfg(feature = "bincode")]
+pub mod shrink;
+#[cfg(feature = "bincode")]
+pub mod strategy;
+pub mod tape;
 pub mod tree;
 mod util;
 pub mod visitor;
+#[cfg(feature = "bincode")]
+pub mod walk;
 
 #[cfg(feature = "thesis_derive")]
 pub use thesis_derive::Grammar;
 #[cfg(feature = "thesis_derive")]
+pub use thesis_derive::Render;
+#[cfg(feature = "thesis_derive")]
 pub use thesis_derive::ToNautilus;
 
+#[cfg(feature = "arbitrary")]
+pub use arbitrary;
+
 #[cfg(feature = "bincode")]
 pub use serde::*;
+#[cfg(feature = "bincode")]
+pub use check::{check, check_with_setup};
+pub use index::NodeIndex;
+pub use mutate::Mutate;
+pub use render::Render;
+pub use tape::generate_from_tape;
 pub use tree::*;
+pub use util::{grow, with_stack};
 pub use visitor::*;
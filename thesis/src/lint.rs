@@ -0,0 +1,555 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{Id, Node};
+
+/// A field of a struct, tuple or enum variant
+#[derive(Debug, Clone)]
+pub struct FieldShape {
+    pub name: &'static str,
+    /// index into `TypeGraph::types`
+    pub ty: usize,
+    /// `#[literal]` and `#[generate_with]` fields are never generated from their type
+    pub custom: bool,
+    /// the `#[literal]` values, as written
+    pub literals: Vec<&'static str>,
+}
+
+impl FieldShape {
+    pub fn new<T: Node>(graph: &mut TypeGraph, name: &'static str, custom: bool) -> Self {
+        Self {
+            name,
+            ty: graph.add::<T>(),
+            custom,
+            literals: vec![],
+        }
+    }
+
+    pub fn with_literals(mut self, literals: Vec<&'static str>) -> Self {
+        self.literals = literals;
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VariantShape {
+    pub name: &'static str,
+    /// marked `#[recursive]`, only chosen while there is generate depth left
+    pub recursive: bool,
+    pub fields: Vec<FieldShape>,
+}
+
+/// How a type is generated, as far as the lints care
+#[derive(Debug, Clone)]
+pub enum Shape {
+    /// numbers, strings, types with `#[generate_with]`: generated without any other node
+    Leaf,
+    /// Box, Rc: generated as the inner type
+    Transparent(usize),
+    /// always has all its elements
+    Array(usize),
+    /// elements are generated at depth 0
+    Vec(usize),
+    /// None ends the recursion
+    Option(usize),
+    Struct(Vec<FieldShape>),
+    Enum(Vec<VariantShape>),
+}
+
+#[derive(Debug, Clone)]
+pub struct TypeInfo {
+    pub name: &'static str,
+    pub shape: Shape,
+    pub id: Id,
+    pub inner_id: Option<Id>,
+}
+
+/// Every type reachable from a root, see `Node::__describe`
+#[derive(Debug, Clone, Default)]
+pub struct TypeGraph {
+    pub types: Vec<TypeInfo>,
+    index: HashMap<&'static str, usize>,
+}
+
+impl TypeGraph {
+    pub fn new<T: Node>() -> Self {
+        let mut graph = Self::default();
+        graph.add::<T>();
+        graph
+    }
+
+    /// The index of `T`, describing it (and what it contains) the first time we see it
+    pub fn add<T: Node>(&mut self) -> usize {
+        let name = std::intrinsics::type_name::<T>();
+        if let Some(index) = self.index.get(name) {
+            return *index;
+        }
+        let index = self.types.len();
+        // placeholder, so recursive types find themselves
+        self.types.push(TypeInfo {
+            name,
+            shape: Shape::Leaf,
+            id: T::id(),
+            inner_id: T::inner_id(),
+        });
+        self.index.insert(name, index);
+        self.types[index].shape = T::__describe(self);
+        index
+    }
+
+    /// `ty` without the Box / Rc around it
+    fn unwrap(&self, mut ty: usize) -> usize {
+        while let Shape::Transparent(inner) = self.types[ty].shape {
+            ty = inner;
+        }
+        ty
+    }
+
+    fn is_transparent(&self, ty: usize) -> bool {
+        matches!(self.types[ty].shape, Shape::Transparent(_))
+    }
+
+    /// The types generating `ty` can step into at any depth.
+    /// `#[literal]`, `#[generate_with]` and computed fields are not generated from their type.
+    fn generate_edges(&self, ty: usize) -> Vec<usize> {
+        let generated = |fields: &Vec<FieldShape>| {
+            fields
+                .iter()
+                .filter(|field| !field.custom)
+                .map(|field| field.ty)
+                .collect::<Vec<_>>()
+        };
+        match &self.types[ty].shape {
+            Shape::Leaf => vec![],
+            Shape::Transparent(inner)
+            | Shape::Array(inner)
+            | Shape::Vec(inner)
+            | Shape::Option(inner) => vec![*inner],
+            Shape::Struct(fields) => generated(fields),
+            Shape::Enum(variants) => variants
+                .iter()
+                .flat_map(|variant| generated(&variant.fields))
+                .collect(),
+        }
+    }
+
+    /// The types generating `ty` can step into at depth 0, ie. without a `#[recursive]` variant
+    /// or the elements of a Vec
+    fn depth_zero_edges(&self, ty: usize) -> Vec<usize> {
+        let generated = |fields: &Vec<FieldShape>| {
+            fields
+                .iter()
+                .filter(|field| !field.custom)
+                .map(|field| field.ty)
+                .collect::<Vec<_>>()
+        };
+        match &self.types[ty].shape {
+            Shape::Leaf | Shape::Vec(_) => vec![],
+            Shape::Transparent(inner) | Shape::Array(inner) | Shape::Option(inner) => vec![*inner],
+            Shape::Struct(fields) => generated(fields),
+            Shape::Enum(variants) => variants
+                .iter()
+                .filter(|variant| !variant.recursive)
+                .flat_map(|variant| generated(&variant.fields))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Lint {
+    /// generating the type at depth 0 never ends
+    NoLeaf { ty: &'static str },
+    /// types that contain each other without a `#[recursive]` variant (or Vec) in between
+    UnmarkedCycle { types: Vec<&'static str> },
+    /// variants we only ever generate below a `#[recursive]` variant, never at generate depth 0
+    UnreachableVariants {
+        ty: &'static str,
+        variants: Vec<&'static str>,
+    },
+    /// the elements of an iterable have the id of the iterable itself (eg. behind a Box or Rc
+    /// that reports the wrong inner id), so splicing an element in appends a whole iterable
+    IdCollision { ty: &'static str },
+    /// a `#[literal]` value that changes when cast (`as`) to the field's type:
+    /// out of range, negative into unsigned, fractional into an integer
+    LiteralCast {
+        ty: &'static str,
+        field: &'static str,
+        field_ty: &'static str,
+        literal: &'static str,
+    },
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Lint::NoLeaf { ty } => write!(
+                f,
+                "{}: can't be generated without a #[recursive] variant, add a variant that ends the recursion",
+                ty
+            ),
+            Lint::UnmarkedCycle { types } => write!(
+                f,
+                "{}: recursive without a #[recursive] variant, depth 0 generation may not end",
+                types.join(" -> ")
+            ),
+            Lint::UnreachableVariants { ty, variants } => write!(
+                f,
+                "{}: {} only generated below a #[recursive] variant, never at generate depth 0",
+                ty,
+                variants.join(", ")
+            ),
+            Lint::IdCollision { ty } => write!(
+                f,
+                "{}: its elements have the same id as itself, splicing them appends a {}",
+                ty, ty
+            ),
+            Lint::LiteralCast {
+                ty,
+                field,
+                field_ty,
+                literal,
+            } => write!(
+                f,
+                "{}.{}: #[literal] {} changes when cast to {}",
+                ty, field, literal, field_ty
+            ),
+        }
+    }
+}
+
+/// Types that can be generated at depth 0: the least fixpoint over the depth 0 rules
+fn terminating(graph: &TypeGraph) -> Vec<bool> {
+    let mut done = vec![false; graph.types.len()];
+    let fields_done = |done: &Vec<bool>, fields: &Vec<FieldShape>| {
+        fields.iter().all(|field| field.custom || done[field.ty])
+    };
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (ty, info) in graph.types.iter().enumerate() {
+            if done[ty] {
+                continue;
+            }
+            let now = match &info.shape {
+                Shape::Leaf | Shape::Vec(_) | Shape::Option(_) => true,
+                Shape::Transparent(inner) | Shape::Array(inner) => done[*inner],
+                Shape::Struct(fields) => fields_done(&done, fields),
+                Shape::Enum(variants) => variants
+                    .iter()
+                    .any(|variant| !variant.recursive && fields_done(&done, &variant.fields)),
+            };
+            if now {
+                done[ty] = true;
+                changed = true;
+            }
+        }
+    }
+    done
+}
+
+/// Tarjan's strongly connected components over the depth 0 edges
+struct Cycles<'a> {
+    graph: &'a TypeGraph,
+    index: Vec<Option<usize>>,
+    low: Vec<usize>,
+    on_stack: Vec<bool>,
+    stack: Vec<usize>,
+    next: usize,
+    cycles: Vec<Vec<usize>>,
+}
+
+impl<'a> Cycles<'a> {
+    fn visit(&mut self, ty: usize) {
+        self.index[ty] = Some(self.next);
+        self.low[ty] = self.next;
+        self.next += 1;
+        self.stack.push(ty);
+        self.on_stack[ty] = true;
+
+        let edges = self.graph.depth_zero_edges(ty);
+        for next in &edges {
+            match self.index[*next] {
+                None => {
+                    self.visit(*next);
+                    self.low[ty] = self.low[ty].min(self.low[*next]);
+                }
+                Some(index) if self.on_stack[*next] => {
+                    self.low[ty] = self.low[ty].min(index);
+                }
+                Some(_) => {}
+            }
+        }
+
+        if Some(self.low[ty]) == self.index[ty] {
+            let mut component = vec![];
+            loop {
+                let member = self.stack.pop().expect("7fPqLm2c____");
+                self.on_stack[member] = false;
+                component.push(member);
+                if member == ty {
+                    break;
+                }
+            }
+            if component.len() > 1 || edges.contains(&ty) {
+                component.reverse();
+                self.cycles.push(component);
+            }
+        }
+    }
+}
+
+fn unmarked_cycles(graph: &TypeGraph) -> Vec<Vec<usize>> {
+    let len = graph.types.len();
+    let mut cycles = Cycles {
+        graph,
+        index: vec![None; len],
+        low: vec![0; len],
+        on_stack: vec![false; len],
+        stack: vec![],
+        next: 0,
+        cycles: vec![],
+    };
+    for ty in 0..len {
+        if cycles.index[ty].is_none() {
+            crate::grow(|| cycles.visit(ty));
+        }
+    }
+    cycles.cycles
+}
+
+/// Which variants of each type we generate at depth 0, starting from the root.
+/// Vec elements count: they are generated at depth 0 whatever the depth of the Vec.
+fn depth_zero_variants(graph: &TypeGraph) -> Vec<Vec<bool>> {
+    let mut reached = graph
+        .types
+        .iter()
+        .map(|info| match &info.shape {
+            Shape::Enum(variants) => vec![false; variants.len()],
+            _ => vec![],
+        })
+        .collect::<Vec<_>>();
+    let mut seen = vec![false; graph.types.len()];
+    let mut todo = vec![0];
+    while let Some(ty) = todo.pop() {
+        if std::mem::replace(&mut seen[ty], true) {
+            continue;
+        }
+        if let Shape::Enum(variants) = &graph.types[ty].shape {
+            for (i, variant) in variants.iter().enumerate() {
+                reached[ty][i] = !variant.recursive;
+            }
+        }
+        todo.extend(graph.depth_zero_edges(ty));
+        if let Shape::Vec(inner) = &graph.types[ty].shape {
+            todo.push(*inner);
+        }
+    }
+    reached
+}
+
+/// The types we ever generate from the root. Types only in custom fields are in the graph
+/// (their chunks are still spliced), but their variants are never chosen by us.
+fn generated_types(graph: &TypeGraph) -> Vec<bool> {
+    let mut seen = vec![false; graph.types.len()];
+    let mut todo = vec![0];
+    while let Some(ty) = todo.pop() {
+        if std::mem::replace(&mut seen[ty], true) {
+            continue;
+        }
+        todo.extend(graph.generate_edges(ty));
+    }
+    seen
+}
+
+/// Iterables whose elements have their own id, wherever they are used (fields, elements,
+/// behind Box / Rc): ids are per type, so we check the types, not the fields.
+fn id_collisions(graph: &TypeGraph) -> Vec<Lint> {
+    graph
+        .types
+        .iter()
+        .enumerate()
+        .filter(|(ty, info)| {
+            matches!(
+                graph.types[graph.unwrap(*ty)].shape,
+                Shape::Vec(_) | Shape::Array(_)
+            ) && info.inner_id.as_ref() == Some(&info.id)
+        })
+        .map(|(_, info)| Lint::IdCollision { ty: info.name })
+        .collect()
+}
+
+/// A numeric `#[literal]`, as far as we can read it from its tokens
+#[derive(Debug, Clone, Copy)]
+enum Number {
+    Int(i128),
+    Float(f64),
+}
+
+/// `literal` is `stringify!`ed: `- 1`, `0xff`, `1_000u16`, `2.5`, `'a'`, `b'a'`, `true`.
+/// `None` for what we can't read (consts, paths, strings), the compiler checks those.
+fn literal_number(literal: &str) -> Option<Number> {
+    let text = literal
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '_')
+        .collect::<String>();
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.as_str()),
+    };
+    let sign = if negative { -1 } else { 1 };
+    let quoted = text
+        .strip_prefix("b'")
+        .or_else(|| text.strip_prefix('\''))
+        .and_then(|text| text.strip_suffix('\''));
+    if let Some(quoted) = quoted {
+        let mut chars = quoted.chars();
+        return match (chars.next(), chars.next(), negative) {
+            (Some(c), None, false) => Some(Number::Int(c as i128)),
+            _ => None,
+        };
+    }
+    match text {
+        "true" if !negative => return Some(Number::Int(1)),
+        "false" if !negative => return Some(Number::Int(0)),
+        _ => {}
+    }
+    let (radix, digits) = match text.get(..2) {
+        Some("0x") => (16, &text[2..]),
+        Some("0o") => (8, &text[2..]),
+        Some("0b") => (2, &text[2..]),
+        _ => (10, text),
+    };
+    // the suffix (`u8`, `i32`, `f64`) starts at the first letter that isn't a digit
+    let end = digits
+        .find(|c: char| !c.is_digit(radix) && !(radix == 10 && matches!(c, '.' | 'e' | 'E')))
+        .unwrap_or(digits.len());
+    let (digits, suffix) = digits.split_at(end);
+    if radix == 10 && (digits.contains(['.', 'e', 'E']) || suffix.starts_with('f')) {
+        return digits
+            .parse::<f64>()
+            .ok()
+            .map(|value| Number::Float(value * sign as f64));
+    }
+    i128::from_str_radix(digits, radix)
+        .ok()
+        .map(|value| Number::Int(value * sign))
+}
+
+/// Does `literal` survive `literal as ty`? Types we don't know (aliases resolve to their
+/// target in the type name, structs don't cast) always do.
+fn literal_fits(literal: &str, ty: &str) -> bool {
+    const INTS: &[(&str, i128, i128)] = &[
+        ("u8", 0, u8::MAX as i128),
+        ("u16", 0, u16::MAX as i128),
+        ("u32", 0, u32::MAX as i128),
+        ("u64", 0, u64::MAX as i128),
+        ("u128", 0, i128::MAX),
+        ("usize", 0, usize::MAX as i128),
+        ("i8", i8::MIN as i128, i8::MAX as i128),
+        ("i16", i16::MIN as i128, i16::MAX as i128),
+        ("i32", i32::MIN as i128, i32::MAX as i128),
+        ("i64", i64::MIN as i128, i64::MAX as i128),
+        ("i128", i128::MIN, i128::MAX),
+        ("isize", isize::MIN as i128, isize::MAX as i128),
+    ];
+    let Some(value) = literal_number(literal) else {
+        return true;
+    };
+    if let Some((_, min, max)) = INTS.iter().find(|(name, _, _)| *name == ty) {
+        return match value {
+            Number::Int(value) => (*min..=*max).contains(&value),
+            Number::Float(value) => {
+                value.fract() == 0.0 && value >= *min as f64 && value <= *max as f64
+            }
+        };
+    }
+    match (ty, value) {
+        ("f32", Number::Int(value)) => value as f32 as i128 == value,
+        ("f64", Number::Int(value)) => value as f64 as i128 == value,
+        ("f32", Number::Float(value)) => value.abs() <= f32::MAX as f64,
+        // only u8 casts to char
+        ("char", Number::Int(value)) => (0..=u8::MAX as i128).contains(&value),
+        _ => true,
+    }
+}
+
+fn literal_casts(graph: &TypeGraph) -> Vec<Lint> {
+    let mut lints = vec![];
+    for info in &graph.types {
+        let fields: Vec<&FieldShape> = match &info.shape {
+            Shape::Struct(fields) => fields.iter().collect(),
+            Shape::Enum(variants) => variants.iter().flat_map(|variant| &variant.fields).collect(),
+            _ => continue,
+        };
+        for field in fields {
+            let field_ty = graph.types[field.ty].name;
+            for literal in &field.literals {
+                if !literal_fits(literal, field_ty) {
+                    lints.push(Lint::LiteralCast {
+                        ty: info.name,
+                        field: field.name,
+                        field_ty,
+                        literal,
+                    });
+                }
+            }
+        }
+    }
+    lints
+}
+
+/// Lint the grammar of `T` without generating anything.
+///
+/// ```ignore
+/// for lint in thesis::lint::lint::<Code>() {
+///     println!("{}", lint);
+/// }
+/// ```
+pub fn lint<T: Node>() -> Vec<Lint> {
+    let graph = TypeGraph::new::<T>();
+    let mut lints = vec![];
+
+    let terminating = terminating(&graph);
+    for (ty, info) in graph.types.iter().enumerate() {
+        if !terminating[ty] && !graph.is_transparent(ty) {
+            lints.push(Lint::NoLeaf { ty: info.name });
+        }
+    }
+
+    for cycle in unmarked_cycles(&graph) {
+        lints.push(Lint::UnmarkedCycle {
+            types: cycle
+                .iter()
+                .filter(|ty| !graph.is_transparent(**ty))
+                .map(|ty| graph.types[*ty].name)
+                .collect(),
+        });
+    }
+
+    let reached = depth_zero_variants(&graph);
+    let generated = generated_types(&graph);
+    for (ty, info) in graph.types.iter().enumerate() {
+        let Shape::Enum(variants) = &info.shape else {
+            continue;
+        };
+        if !generated[ty] {
+            continue;
+        }
+        let unreachable = variants
+            .iter()
+            .zip(&reached[ty])
+            .filter(|(variant, reached)| !variant.recursive && !**reached)
+            .map(|(variant, _)| variant.name)
+            .collect::<Vec<_>>();
+        if !unreachable.is_empty() {
+            lints.push(Lint::UnreachableVariants {
+                ty: info.name,
+                variants: unreachable,
+            });
+        }
+    }
+
+    lints.extend(id_collisions(&graph));
+    lints.extend(literal_casts(&graph));
+    lints
+}
@@ -0,0 +1,87 @@
+use std::any::Any;
+
+use crate::Visitor;
+
+/// Small in-place changes to a leaf, on top of `GenerateReplace` and `Splice`
+/// (eg. increment a number, flip the case of a char).
+///
+/// Implement it for your own leaf types and register them with `Visitor::register_mutate`,
+/// the tweak mutator then picks nodes of the registered types and calls `mutate` on them.
+pub trait Mutate {
+    fn mutate(&mut self, visitor: &mut Visitor);
+}
+
+/// What `Visitor` stores per registered type, so it can call `Mutate` without knowing `T`
+pub(crate) type Tweak = fn(&mut dyn Any, &mut Visitor) -> bool;
+
+pub(crate) fn tweak<T: Mutate + 'static>(node: &mut dyn Any, visitor: &mut Visitor) -> bool {
+    // Box<T> and Rc<T> share the id of T, we only tweak the registered type itself
+    match node.downcast_mut::<T>() {
+        Some(node) => {
+            node.mutate(visitor);
+            true
+        }
+        None => false,
+    }
+}
+
+impl Mutate for bool {
+    fn mutate(&mut self, visitor: &mut Visitor) {
+        *self = !*self;
+    }
+}
+
+macro_rules! impl_mutate_int {
+    ($($type: ty),*) => {
+        $(
+            impl Mutate for $type {
+                fn mutate(&mut self, visitor: &mut Visitor) {
+                    match visitor.random_range(0, 2) {
+                        0 => *self = self.wrapping_add(visitor.random_range(1, 16) as $type),
+                        1 => *self = self.wrapping_sub(visitor.random_range(1, 16) as $type),
+                        _ => *self ^= 1 << visitor.random_range(0, <$type>::BITS as usize - 1),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_mutate_int!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+impl Mutate for String {
+    fn mutate(&mut self, visitor: &mut Visitor) {
+        let printables = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz".as_bytes();
+        let chars = self.chars().count();
+        match visitor.random_range(0, 2) {
+            // flip the case of a char
+            0 if chars > 0 => {
+                let flip = visitor.random_range(0, chars - 1);
+                *self = self
+                    .chars()
+                    .enumerate()
+                    .map(|(i, c)| match i == flip {
+                        true if c.is_uppercase() => c.to_ascii_lowercase(),
+                        true => c.to_ascii_uppercase(),
+                        false => c,
+                    })
+                    .collect();
+            }
+            // remove a char
+            1 if chars > 0 => {
+                let remove = visitor.random_range(0, chars - 1);
+                *self = self
+                    .chars()
+                    .enumerate()
+                    .filter(|(i, _)| *i != remove)
+                    .map(|(_, c)| c)
+                    .collect();
+            }
+            // append a char
+            _ => {
+                let c = printables[visitor.random_range(0, printables.len() - 1)];
+                self.push(c as char);
+            }
+        }
+    }
+}
@@ -0,0 +1,156 @@
+//! Grammars for types from other crates (eg. a parser's AST), like serde's remote derive.
+//!
+//! We can't implement `Node` for a foreign type, so we fuzz a local mirror of it instead and
+//! convert when we need the real thing (eg. to render it with the other crate's printer):
+//!
+//! ```ignore
+//! #[derive(Debug, Clone, Serialize, Deserialize, thesis::Grammar)]
+//! #[thesis(remote = "parser::ast::Expr")]
+//! enum ExprDef {
+//!     Number(u32),
+//!     #[recursive]
+//!     Add(Box<ExprDef>, Box<ExprDef>),
+//! }
+//!
+//! let expr: parser::ast::Expr = mirror.into();
+//! ```
+//!
+//! The mirror must have the same fields and variants (by name) as the remote type, with the
+//! remote types inside it replaced by their mirrors.
+
+use std::rc::Rc;
+
+/// A mirror of `Self::Remote`, derived with `#[thesis(remote = "...")]`
+pub trait Remote: Sized {
+    type Remote;
+
+    fn into_remote(self) -> Self::Remote;
+
+    fn from_remote(remote: Self::Remote) -> Self;
+}
+
+macro_rules! impl_remote_identity {
+    ($($type: ty),*) => {
+        $(
+            impl Remote for $type {
+                type Remote = Self;
+
+                fn into_remote(self) -> Self {
+                    self
+                }
+
+                fn from_remote(remote: Self) -> Self {
+                    remote
+                }
+            }
+        )*
+    };
+}
+
+impl_remote_identity!(
+    bool, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64, String
+);
+
+impl<T: Remote> Remote for Vec<T> {
+    type Remote = Vec<T::Remote>;
+
+    fn into_remote(self) -> Self::Remote {
+        self.into_iter().map(T::into_remote).collect()
+    }
+
+    fn from_remote(remote: Self::Remote) -> Self {
+        remote.into_iter().map(T::from_remote).collect()
+    }
+}
+
+impl<T: Remote, const N: usize> Remote for [T; N] {
+    type Remote = [T::Remote; N];
+
+    fn into_remote(self) -> Self::Remote {
+        self.map(T::into_remote)
+    }
+
+    fn from_remote(remote: Self::Remote) -> Self {
+        remote.map(T::from_remote)
+    }
+}
+
+impl<T: Remote> Remote for Box<T> {
+    type Remote = Box<T::Remote>;
+
+    fn into_remote(self) -> Self::Remote {
+        Box::new((*self).into_remote())
+    }
+
+    fn from_remote(remote: Self::Remote) -> Self {
+        Box::new(T::from_remote(*remote))
+    }
+}
+
+impl<T: Remote + Clone> Remote for Rc<T>
+where
+    T::Remote: Clone,
+{
+    type Remote = Rc<T::Remote>;
+
+    fn into_remote(self) -> Self::Remote {
+        Rc::new(Rc::unwrap_or_clone(self).into_remote())
+    }
+
+    fn from_remote(remote: Self::Remote) -> Self {
+        Rc::new(T::from_remote(Rc::unwrap_or_clone(remote)))
+    }
+}
+
+impl<T: Remote> Remote for Option<T> {
+    type Remote = Option<T::Remote>;
+
+    fn into_remote(self) -> Self::Remote {
+        self.map(T::into_remote)
+    }
+
+    fn from_remote(remote: Self::Remote) -> Self {
+        remote.map(T::from_remote)
+    }
+}
+
+impl<T: Remote, E: Remote> Remote for Result<T, E> {
+    type Remote = Result<T::Remote, E::Remote>;
+
+    fn into_remote(self) -> Self::Remote {
+        self.map(T::into_remote).map_err(E::into_remote)
+    }
+
+    fn from_remote(remote: Self::Remote) -> Self {
+        remote.map(T::from_remote).map_err(E::from_remote)
+    }
+}
+
+macro_rules! tuple_remote {
+    ( $( ($T:ident , $id:tt)),+ ) => {
+        impl<$($T: Remote),+> Remote for ($($T,)+) {
+            type Remote = ($($T::Remote,)+);
+
+            fn into_remote(self) -> Self::Remote {
+                ($(self.$id.into_remote(),)+)
+            }
+
+            fn from_remote(remote: Self::Remote) -> Self {
+                ($($T::from_remote(remote.$id),)+)
+            }
+        }
+    };
+}
+
+tuple_remote! { (A, 0) }
+tuple_remote! { (A, 0), (B, 1) }
+tuple_remote! { (A, 0), (B, 1), (C, 2) }
+tuple_remote! { (A, 0), (B, 1), (C, 2), (D, 3) }
+tuple_remote! { (A, 0), (B, 1), (C, 2), (D, 3), (E, 4) }
+tuple_remote! { (A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5) }
+tuple_remote! { (A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6) }
+tuple_remote! { (A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6), (H, 7) }
+tuple_remote! { (A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6), (H, 7), (I, 8) }
+tuple_remote! { (A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6), (H, 7), (I, 8), (J, 9) }
+tuple_remote! { (A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6), (H, 7), (I, 8), (J, 9), (K, 10) }
+tuple_remote! { (A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6), (H, 7), (I, 8), (J, 9), (K, 10), (L, 11) }
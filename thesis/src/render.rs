@@ -0,0 +1,290 @@
+//! Target bytes for binary formats (file headers, packets, ...), see `#[derive(thesis::Render)]`.
+//!
+//! Lengths, counts, offsets and checksums only mean something once the tree is final, so we
+//! don't fuzz them: fields marked with one of the attributes below are left out of mutation
+//! and computed when the tree is rendered.
+//!
+//! ```ignore
+//! #[derive(Debug, Clone, Serialize, Deserialize, thesis::Grammar, thesis::Render)]
+//! #[be]
+//! struct Chunk {
+//!     #[len_of(data)]
+//!     length: u32,
+//!     #[literal(0x49484452, 0x49444154)]
+//!     kind: u32,
+//!     data: Vec<u8>,
+//!     #[checksum(crc32, over = (kind, data))]
+//!     crc: u32,
+//! }
+//!
+//! impl_converter!(Chunk, |data: Chunk| data.to_bytes());
+//! ```
+//!
+//! - `#[len_of(field)]`: the length of `field` in bytes, as rendered
+//! - `#[count_of(field)]`: the number of elements of `field` (its `len()`)
+//! - `#[offset_of(field)]`: where `field` starts, from the start of the struct (or variant)
+//! - `#[checksum(crc32 | adler32 | path::to::fn, over = field)]` or `over = (a, b, ...)`:
+//!   the checksum of the fields, rendered one after the other. `fn(&[u8]) -> u32` (or any integer)
+//!   for your own algorithm. Checksums are computed in field order, so one can cover another
+//!   one above it.
+//! - `#[be]` / `#[le]`: render the numbers of a field big / little endian. On a type (or variant)
+//!   it sets the order of its number fields (and Vecs, arrays, Options of numbers).
+//!   Without either, numbers are little endian.
+//!
+//! Fields are rendered one after the other, without length prefixes or enum tags: a format that
+//! has them declares them as fields (eg. a `#[literal]` tag in every variant).
+
+use std::rc::Rc;
+
+/// The bytes of a tree in the target format
+pub trait Render {
+    /// Append our bytes to `out`
+    fn render(&self, out: &mut Vec<u8>);
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![];
+        self.render(&mut out);
+        out
+    }
+}
+
+/// Integers (and what contains them) in a fixed byte order, for `#[be]` and `#[le]` fields
+pub trait Endian {
+    fn render_be(&self, out: &mut Vec<u8>);
+
+    fn render_le(&self, out: &mut Vec<u8>);
+}
+
+/// What computed fields are built from, the value is truncated to the field like a real
+/// encoder would (eg. a `#[len_of]` u8 of a 300 byte field is 44)
+pub trait FromComputed {
+    fn from_computed(value: u64) -> Self;
+}
+
+macro_rules! impl_render_number {
+    ($($type: ty),*) => {
+        $(
+            impl Render for $type {
+                fn render(&self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_le_bytes());
+                }
+            }
+
+            impl Endian for $type {
+                fn render_be(&self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_be_bytes());
+                }
+
+                fn render_le(&self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_render_number!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+macro_rules! impl_from_computed {
+    ($($type: ty),*) => {
+        $(
+            impl FromComputed for $type {
+                fn from_computed(value: u64) -> Self {
+                    value as $type
+                }
+            }
+        )*
+    };
+}
+
+impl_from_computed!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+impl Render for bool {
+    fn render(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+}
+
+impl Render for char {
+    fn render(&self, out: &mut Vec<u8>) {
+        let mut buffer = [0; 4];
+        out.extend_from_slice(self.encode_utf8(&mut buffer).as_bytes());
+    }
+}
+
+impl Render for String {
+    fn render(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self.as_bytes());
+    }
+}
+
+impl<T: Render> Render for Vec<T> {
+    fn render(&self, out: &mut Vec<u8>) {
+        for element in self {
+            element.render(out);
+        }
+    }
+}
+
+impl<T: Render, const N: usize> Render for [T; N] {
+    fn render(&self, out: &mut Vec<u8>) {
+        for element in self {
+            element.render(out);
+        }
+    }
+}
+
+impl<T: Render> Render for Box<T> {
+    fn render(&self, out: &mut Vec<u8>) {
+        (**self).render(out);
+    }
+}
+
+impl<T: Render> Render for Rc<T> {
+    fn render(&self, out: &mut Vec<u8>) {
+        (**self).render(out);
+    }
+}
+
+/// None is left out
+impl<T: Render> Render for Option<T> {
+    fn render(&self, out: &mut Vec<u8>) {
+        if let Some(inner) = self {
+            inner.render(out);
+        }
+    }
+}
+
+impl<T: Endian> Endian for Vec<T> {
+    fn render_be(&self, out: &mut Vec<u8>) {
+        for element in self {
+            element.render_be(out);
+        }
+    }
+
+    fn render_le(&self, out: &mut Vec<u8>) {
+        for element in self {
+            element.render_le(out);
+        }
+    }
+}
+
+impl<T: Endian, const N: usize> Endian for [T; N] {
+    fn render_be(&self, out: &mut Vec<u8>) {
+        for element in self {
+            element.render_be(out);
+        }
+    }
+
+    fn render_le(&self, out: &mut Vec<u8>) {
+        for element in self {
+            element.render_le(out);
+        }
+    }
+}
+
+impl<T: Endian> Endian for Box<T> {
+    fn render_be(&self, out: &mut Vec<u8>) {
+        (**self).render_be(out);
+    }
+
+    fn render_le(&self, out: &mut Vec<u8>) {
+        (**self).render_le(out);
+    }
+}
+
+impl<T: Endian> Endian for Option<T> {
+    fn render_be(&self, out: &mut Vec<u8>) {
+        if let Some(inner) = self {
+            inner.render_be(out);
+        }
+    }
+
+    fn render_le(&self, out: &mut Vec<u8>) {
+        if let Some(inner) = self {
+            inner.render_le(out);
+        }
+    }
+}
+
+macro_rules! tuple_render {
+    ( $( ($T:ident , $id:tt)),+ ) => {
+        impl<$($T: Render),+> Render for ($($T,)+) {
+            fn render(&self, out: &mut Vec<u8>) {
+                $(self.$id.render(out);)+
+            }
+        }
+    };
+}
+
+tuple_render! { (A, 0) }
+tuple_render! { (A, 0), (B, 1) }
+tuple_render! { (A, 0), (B, 1), (C, 2) }
+tuple_render! { (A, 0), (B, 1), (C, 2), (D, 3) }
+tuple_render! { (A, 0), (B, 1), (C, 2), (D, 3), (E, 4) }
+tuple_render! { (A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5) }
+tuple_render! { (A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6) }
+tuple_render! { (A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6), (H, 7) }
+tuple_render! { (A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6), (H, 7), (I, 8) }
+tuple_render! { (A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6), (H, 7), (I, 8), (J, 9) }
+tuple_render! { (A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6), (H, 7), (I, 8), (J, 9), (K, 10) }
+tuple_render! { (A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6), (H, 7), (I, 8), (J, 9), (K, 10), (L, 11) }
+
+/// CRC-32 (IEEE 802.3, as in zlib, PNG and gzip)
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Adler-32 (as in zlib streams)
+pub fn adler32(bytes: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in bytes {
+        a = (a + *byte as u32) % MOD;
+        b = (b + a) % MOD;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32_vectors() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"a"), 0xE8B7_BE43);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(
+            crc32(b"The quick brown fox jumps over the lazy dog"),
+            0x414F_A339
+        );
+    }
+
+    #[test]
+    fn adler32_vectors() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"a"), 0x0062_0062);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+        assert_eq!(adler32(&[0xff; 6000]), {
+            // long enough for both sums to wrap
+            let (mut a, mut b) = (1u64, 0u64);
+            for _ in 0..6000 {
+                a += 0xff;
+                b += a;
+            }
+            ((b % 65521) << 16 | a % 65521) as u32
+        });
+    }
+}
@@ -1,6 +1,6 @@
 use std::{collections::VecDeque, fmt::Debug};
 
-use crate::{deserialize, serialize, MutationType, Node, Visitor};
+use crate::{deserialize, serialize, walk::Walk, MutationType, Node, Visitor};
 
 #[cfg(feature = "bincode")]
 macro_rules! impl_node_serde_array {
@@ -20,37 +20,10 @@ macro_rules! impl_node_serde_array {
                     .expect("invariant;")
             }
 
-            #[cfg(debug_assertions)]
-            fn serialized(&self) -> Option<Vec<(Vec<u8>, String)>> {
-                let mut vector = self
-                    .iter()
-                    .map(|i| (serialize(i), T::id()))
-                    .collect::<Vec<_>>();
-                for item in self.iter() {
-                    if let Some(inner) = item.serialized() {
-                        vector.extend(inner)
-                    }
-                }
-                Some(vector)
-            }
-
             fn __len(&self) -> usize {
                 $n
             }
 
-            #[cfg(not(debug_assertions))]
-            fn serialized(&self) -> Option<Vec<(Vec<u8>, u128)>> {
-                let mut vector = self
-                    .iter()
-                    .map(|i| (serialize(i), T::id()))
-                    .collect::<Vec<_>>();
-                for item in self.iter() {
-                    if let Some(inner) = item.serialized() {
-                        vector.extend(inner)
-                    }
-                }
-                Some(vector)
-            }
             fn __mutate(
                 &mut self,
                 ty: &mut MutationType,
@@ -69,6 +42,9 @@ macro_rules! impl_node_serde_array {
                         MutationType::GenerateReplace(ref mut bias) => {
                             *self = Self::generate(visitor, bias, &mut 0)
                         }
+                        MutationType::Tweak(ref mut tweaked) => {
+                            *tweaked = visitor.tweak(self);
+                        }
                         _ => {
                             // TODO: FIX: cause our length is fixed, we cannot append but we cannot be unreachable
                             // since we are recursive, we may still get called
@@ -93,6 +69,30 @@ macro_rules! impl_node_serde_array {
                     visitor.pop_field();
                 }
             }
+
+            fn observe(&self, visitor: &mut Visitor) {
+                for child in self.iter() {
+                    child.observe(visitor);
+                }
+            }
+
+            fn __walk(&self, walker: &mut dyn Walk, index: usize) {
+                walker.enter(index, self);
+                for (index, child) in self.iter().enumerate() {
+                    child.__walk(walker, index);
+                }
+                walker.leave(self);
+            }
+
+            fn __describe(graph: &mut crate::lint::TypeGraph) -> crate::lint::Shape {
+                crate::lint::Shape::Array(graph.add::<T>())
+            }
+
+            fn __rust_expr(&self, out: &mut String) {
+                out.push('[');
+                crate::tree::rust_exprs(self.iter(), out);
+                out.push(']');
+            }
         }
     };
 }
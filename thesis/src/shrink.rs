@@ -0,0 +1,66 @@
+use std::collections::VecDeque;
+
+use crate::{serialize, MutationType, Node, NodeType, Visitor};
+
+/// A tree-aware shrinking step.
+/// These are the same moves the minimization stages in the fuzzer make.
+#[derive(Debug, Clone)]
+pub enum Shrink {
+    /// remove the element at index from the iterable at path
+    IterablePop(VecDeque<usize>, usize),
+    /// replace the recursive node at path with a non-recursive one
+    RecursiveReplace(VecDeque<usize>),
+}
+
+/// All shrinking steps for `input`, outermost nodes first.
+pub fn shrinks<T>(input: &T, visitor: &mut Visitor) -> Vec<Shrink>
+where
+    T: Node,
+{
+    let mut shrinks = vec![];
+    // the root is never registered as a node
+    for index in 0..input.__len() {
+        shrinks.push(Shrink::IterablePop(VecDeque::new(), index));
+    }
+    if input.is_recursive() {
+        shrinks.push(Shrink::RecursiveReplace(VecDeque::new()));
+    }
+    input.nodes(visitor, 0);
+    let nodes = visitor.node_index();
+    for node in 0..nodes.len() {
+        let path = nodes.path(node);
+        let ((id, node_ty), ty) = nodes.get(node);
+        match node_ty {
+            NodeType::Iterable(size, _) => {
+                for index in 0..=*size {
+                    shrinks.push(Shrink::IterablePop(path.clone(), index));
+                }
+            }
+            NodeType::Recursive => shrinks.push(Shrink::RecursiveReplace(path)),
+            NodeType::NonRecursive => {}
+        }
+    }
+    shrinks
+}
+
+/// Apply `shrink` to a copy of `input`.
+/// Returns None if the step did not change anything (eg. popping from a fixed size array).
+pub fn apply<T>(input: &T, shrink: &Shrink, visitor: &mut Visitor) -> Option<T>
+where
+    T: Node + Clone,
+{
+    let mut shrunk = input.clone();
+    match shrink {
+        Shrink::IterablePop(path, index) => {
+            shrunk.__mutate(&mut MutationType::IterablePop(*index), visitor, path.clone())
+        }
+        Shrink::RecursiveReplace(path) => {
+            shrunk.__mutate(&mut MutationType::RecursiveReplace, visitor, path.clone())
+        }
+    }
+    if serialize(&shrunk) == serialize(input) {
+        None
+    } else {
+        Some(shrunk)
+    }
+}
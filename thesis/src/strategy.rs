@@ -0,0 +1,136 @@
+//! Use `Grammar` types in property tests.
+//! Generation goes through `Node::generate`, shrinking through `shrink`.
+use std::marker::PhantomData;
+
+use crate::{shrink, DepthInfo, Node, Visitor};
+
+#[cfg(feature = "proptest")]
+pub use self::proptest_impl::*;
+
+#[cfg(feature = "proptest")]
+mod proptest_impl {
+    use super::*;
+    use proptest::{
+        strategy::{NewTree, Strategy, ValueTree},
+        test_runner::TestRunner,
+    };
+
+    /// proptest `Strategy` for any `Node`.
+    #[derive(Debug, Clone)]
+    pub struct GrammarStrategy<T> {
+        depth: DepthInfo,
+        phantom: PhantomData<T>,
+    }
+
+    impl<T> GrammarStrategy<T> {
+        pub fn new(depth: DepthInfo) -> Self {
+            Self {
+                depth,
+                phantom: PhantomData,
+            }
+        }
+    }
+
+    /// `GrammarStrategy` with the default depth.
+    pub fn any<T>() -> GrammarStrategy<T> {
+        GrammarStrategy::new(DepthInfo::default())
+    }
+
+    impl<T> Strategy for GrammarStrategy<T>
+    where
+        T: Node + Clone,
+    {
+        type Tree = GrammarValueTree<T>;
+        type Value = T;
+
+        fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+            let seed = proptest::num::u64::ANY.new_tree(runner)?.current();
+            let mut visitor = Visitor::new(seed, self.depth.clone());
+            let mut depth = visitor.generate_depth();
+            let current = T::generate(&mut visitor, &mut depth, &mut 0);
+            let shrinks = shrink::shrinks(&current, &mut visitor);
+            Ok(GrammarValueTree {
+                current,
+                shrinks,
+                previous: None,
+                visitor,
+            })
+        }
+    }
+
+    pub struct GrammarValueTree<T> {
+        current: T,
+        /// steps we have not tried on current yet
+        shrinks: Vec<shrink::Shrink>,
+        /// what to go back to if the last simplification made the test pass
+        previous: Option<(T, Vec<shrink::Shrink>)>,
+        visitor: Visitor,
+    }
+
+    impl<T> ValueTree for GrammarValueTree<T>
+    where
+        T: Node + Clone,
+    {
+        type Value = T;
+
+        fn current(&self) -> T {
+            self.current.clone()
+        }
+
+        fn simplify(&mut self) -> bool {
+            while !self.shrinks.is_empty() {
+                // outermost first, those remove the most
+                let step = self.shrinks.remove(0);
+                if let Some(shrunk) = shrink::apply(&self.current, &step, &mut self.visitor) {
+                    let shrinks = shrink::shrinks(&shrunk, &mut self.visitor);
+                    let previous = std::mem::replace(&mut self.current, shrunk);
+                    let previous_shrinks = std::mem::replace(&mut self.shrinks, shrinks);
+                    self.previous = Some((previous, previous_shrinks));
+                    return true;
+                }
+            }
+            false
+        }
+
+        fn complicate(&mut self) -> bool {
+            if let Some((previous, shrinks)) = self.previous.take() {
+                self.current = previous;
+                self.shrinks = shrinks;
+                true
+            } else {
+                false
+            }
+        }
+    }
+}
+
+/// Wrapper to use any `Node` as a quickcheck `Arbitrary`.
+/// eg: `fn prop(code: Arb<Code>) -> bool`
+#[cfg(feature = "quickcheck")]
+#[derive(Debug, Clone)]
+pub struct Arb<T>(pub T);
+
+#[cfg(feature = "quickcheck")]
+impl<T> quickcheck::Arbitrary for Arb<T>
+where
+    T: Node + Clone,
+{
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        let seed = <u64 as quickcheck::Arbitrary>::arbitrary(g);
+        let mut visitor = Visitor::new(seed, DepthInfo::default());
+        let mut depth = visitor.generate_depth();
+        Arb(T::generate(&mut visitor, &mut depth, &mut 0))
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        let mut visitor = Visitor::new(0, DepthInfo::default());
+        let input = self.0.clone();
+        let shrinks = shrink::shrinks(&input, &mut visitor);
+        Box::new(
+            shrinks
+                .into_iter()
+                .filter_map(move |step| shrink::apply(&input, &step, &mut visitor))
+                .map(Arb),
+        )
+    }
+}
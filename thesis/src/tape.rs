@@ -0,0 +1,81 @@
+use crate::{DepthInfo, Node, Visitor};
+
+/// A byte tape the visitor reads its choices from instead of the rng.
+/// (parametric generators a la Zest/JQF)
+///
+/// Every tape decodes to a valid tree: once the tape runs out we read zeroes,
+/// which always picks the smallest / non-recursive choice, so generation terminates.
+#[derive(Debug, Clone)]
+pub struct Tape {
+    data: Vec<u8>,
+    position: usize,
+}
+
+impl Tape {
+    pub fn new(data: Vec<u8>) -> Self {
+        Self { data, position: 0 }
+    }
+
+    pub fn next_byte(&mut self) -> u8 {
+        let byte = self.data.get(self.position).copied().unwrap_or(0);
+        self.position += 1;
+        byte
+    }
+
+    /// little endian u64 out of the next `amount` (<= 8) bytes
+    pub fn next_u64(&mut self, amount: usize) -> u64 {
+        (0..amount).fold(0u64, |acc, i| acc | (self.next_byte() as u64) << (i * 8))
+    }
+
+    pub fn next_bytes(&mut self, amount: usize) -> Vec<u8> {
+        (0..amount).map(|_| self.next_byte()).collect()
+    }
+
+    /// how many bytes we have read (may be larger than the tape)
+    pub fn consumed(&self) -> usize {
+        self.position
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+/// Decode `tape` into a `T` using the visitor's depth settings and the string pool it was seeded with.
+/// Learned weights, swarm testing and strings registered later (dicts, cmplog) are ignored,
+/// so a tape decodes to the same tree for as long as the visitor lives.
+/// NOTE: the seeded pool depends on the visitor's seed, so use the same seed to replay a tape.
+pub fn generate_from_tape<T>(visitor: &mut Visitor, tape: Vec<u8>) -> T
+where
+    T: Node,
+{
+    visitor.set_tape(tape);
+    let generated = T::generate(visitor, &mut visitor.generate_depth(), &mut 0);
+    visitor.take_tape();
+    generated
+}
+
+/// `arbitrary::Arbitrary` for every `Grammar` type goes through here.
+/// Each call decodes with a fresh visitor seeded from `u`, so the result only depends on the bytes.
+#[cfg(feature = "arbitrary")]
+pub fn arbitrary<'a, T>(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<T>
+where
+    T: Node,
+{
+    let seed = u.arbitrary::<u64>()?;
+    let len = u.arbitrary_len::<u8>()?;
+    let tape = u.bytes(len)?.to_vec();
+    let mut visitor = Visitor::new(seed, DepthInfo::default());
+    Ok(generate_from_tape(&mut visitor, tape))
+}
+
+#[cfg(feature = "arbitrary")]
+pub fn arbitrary_take_rest<'a, T>(mut u: arbitrary::Unstructured<'a>) -> arbitrary::Result<T>
+where
+    T: Node,
+{
+    let seed = u.arbitrary::<u64>()?;
+    let tape = u.take_rest().to_vec();
+    let mut visitor = Visitor::new(seed, DepthInfo::default());
+    Ok(generate_from_tape(&mut visitor, tape))
+}
@@ -2,9 +2,13 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 #[cfg(feature = "scale")]
 use parity_scale_codec::{Decode, Encode};
-use std::{collections::VecDeque, fmt::Debug};
+use std::{collections::VecDeque, fmt::Debug, rc::Rc};
 
-use crate::{NodeType, Visitor};
+#[cfg(feature = "bincode")]
+use crate::lint::{FieldShape, Shape, TypeGraph, VariantShape};
+#[cfg(feature = "bincode")]
+use crate::walk::Walk;
+use crate::{NodeType, Visitor, MAX_DEPTH};
 
 #[cfg(debug_assertions)]
 pub type Id = std::string::String;
@@ -19,6 +23,9 @@ pub enum MutationType<'a> {
     RecursiveReplace,
     Splice(&'a mut &'a [u8]),
     SpliceAppend(&'a mut &'a [u8]),
+    /// `Mutate::mutate` the node, if its type is registered with `Visitor::register_mutate`.
+    /// Start with `false`, it is set to whether the node was tweaked.
+    Tweak(bool),
 }
 
 #[cfg(feature = "bincode")]
@@ -46,14 +53,49 @@ where
     fn nodes(&self, visitor: &mut Visitor, index: usize) {}
 
     fn cmps(&self, visitor: &mut Visitor, index: usize, val: (u64, u64)) {}
-    
+
+    /// Report the variants (and strings) used in this tree to the visitor,
+    /// so it can learn which choices lead to interesting inputs.
+    fn observe(&self, visitor: &mut Visitor) {}
+
+    /// Walk this node and its children, see `walk::Walk`.
+    fn __walk(&self, walker: &mut dyn Walk, index: usize) {
+        walker.enter(index, self);
+        walker.leave(self);
+    }
+
+    /// How `generate` builds us, see `lint::TypeGraph`
+    fn __describe(graph: &mut TypeGraph) -> Shape {
+        Shape::Leaf
+    }
+
+    /// Append `to_rust_expr` to `out`, Debug is the expression for most leaves
+    fn __rust_expr(&self, out: &mut String) {
+        out.push_str(&format!("{:?}", self));
+    }
+
+    /// Rust source that builds this value, eg. to paste a crash into a regression test:
+    /// `Statement::If(Expression::Literal(..), Body(vec![..]), None, None)`.
+    /// Types are named without their module, so the test needs them in scope.
+    fn to_rust_expr(&self) -> String {
+        let mut out = String::new();
+        crate::grow(|| self.__rust_expr(&mut out));
+        out
+    }
     
     fn is_recursive(&self) -> bool {
         false
     }
 
+    /// Every subtree we can splice with (all nodes but us and iterables) serialized, with its type.
+    /// We serialize once and slice the subtrees out, see `chunks::Offsets`.
     fn serialized(&self) -> Option<Vec<(Vec<u8>, Id)>> {
-        Some(vec![(serialize(&self), Self::id())])
+        Some(crate::chunks::chunks(self))
+    }
+
+    /// Bytes bincode writes before our children: enum tag, length prefix or Option tag.
+    fn __header_len(&self) -> usize {
+        0
     }
 
     fn __mutate(&mut self, ty: &mut MutationType, visitor: &mut Visitor, path: VecDeque<usize>) {
@@ -65,6 +107,9 @@ where
             MutationType::GenerateReplace(ref mut bias) => {
                 *self = Self::generate(visitor, bias, &mut 0);
             }
+            MutationType::Tweak(ref mut tweaked) => {
+                *tweaked = visitor.tweak(self);
+            }
             _ => {
                 unreachable!()
             }
@@ -296,21 +341,13 @@ where
         self.len()
     }
 
-    fn inner_id() -> Option<Id> {
-        Some(T::id())
+    /// u64 length
+    fn __header_len(&self) -> usize {
+        8
     }
 
-    fn serialized(&self) -> Option<Vec<(Vec<u8>, Id)>> {
-        let mut vector = self
-            .iter()
-            .map(|i| (serialize(i), T::id()))
-            .collect::<Vec<_>>();
-        for item in self.iter() {
-            if let Some(inner) = item.serialized() {
-                vector.extend(inner)
-            }
-        }
-        Some(vector)
+    fn inner_id() -> Option<Id> {
+        Some(T::id())
     }
 
     fn __mutate(
@@ -343,6 +380,9 @@ where
                 MutationType::RecursiveReplace => {
                     // TODO
                 }
+                MutationType::Tweak(ref mut tweaked) => {
+                    *tweaked = visitor.tweak(self);
+                }
             }
         }
     }
@@ -369,6 +409,30 @@ where
             visitor.pop_field();
         }
     }
+
+    fn observe(&self, visitor: &mut Visitor) {
+        for child in self {
+            child.observe(visitor);
+        }
+    }
+
+    fn __walk(&self, walker: &mut dyn Walk, index: usize) {
+        walker.enter(index, self);
+        for (index, child) in self.iter().enumerate() {
+            child.__walk(walker, index);
+        }
+        walker.leave(self);
+    }
+
+    fn __describe(graph: &mut TypeGraph) -> Shape {
+        Shape::Vec(graph.add::<T>())
+    }
+
+    fn __rust_expr(&self, out: &mut String) {
+        out.push_str("vec![");
+        rust_exprs(self.iter(), out);
+        out.push(']');
+    }
 }
 
 impl Node for bool {
@@ -387,12 +451,27 @@ where
         Box::new(T::generate(visitor, depth, cur_depth))
     }
 
+    /// Box is transparent (same encoding, walked as T), so T's chunks fit wherever a Box<T> goes.
+    /// A `Box<T>` field is registered, stored in the chunks and spliced as a `T`: chunks are
+    /// collected by walking the tree, which never sees the Box, so with an id of its own
+    /// a boxed field would have no chunks to splice in.
+    ///
+    /// NOTE: ids name the `chunks/` and `cmps/` directories of a fuzzer's output. Ones written
+    /// before Box and Rc shared the id of T have directories named after `Box<T>` / `Rc<T>`
+    /// that nothing reads any more: delete them. Corpus files are named by their structural
+    /// hash, which never saw the Box, so they keep their names and still load.
+    fn id() -> Id {
+        T::id()
+    }
+
     fn __len(&self) -> usize {
         self.as_ref().__len()
     }
 
+    /// the elements of the iterable we wrap, not the iterable itself: with T's id
+    /// the elements of a `Box<Vec<X>>` would have the id of the whole Vec
     fn inner_id() -> Option<Id> {
-        Some(T::id())
+        T::inner_id()
     }
 
     fn cmps(&self, visitor: &mut Visitor, index: usize, val: (u64, u64)) {
@@ -403,12 +482,89 @@ where
         self.as_ref().nodes(visitor, index);
     }
 
+    fn observe(&self, visitor: &mut Visitor) {
+        self.as_ref().observe(visitor);
+    }
+
+    fn __walk(&self, walker: &mut dyn Walk, index: usize) {
+        self.as_ref().__walk(walker, index);
+    }
+
+    fn __header_len(&self) -> usize {
+        self.as_ref().__header_len()
+    }
+
     fn __mutate(&mut self, ty: &mut MutationType, visitor: &mut Visitor, path: VecDeque<usize>) {
         self.as_mut().__mutate(ty, visitor, path);
     }
+    fn __describe(graph: &mut TypeGraph) -> Shape {
+        Shape::Transparent(graph.add::<T>())
+    }
 
-    fn serialized(&self) -> Option<Vec<(Vec<u8>, Id)>> {
-        self.as_ref().serialized()
+    fn __rust_expr(&self, out: &mut String) {
+        out.push_str("Box::new(");
+        self.as_ref().__rust_expr(out);
+        out.push(')');
+    }
+}
+
+/// Use `Rc<T>` instead of `Box<T>` to share subtrees between clones of an input.
+/// Cloning the input only copies the pointers, `__mutate` copies the nodes on the mutated path
+/// (`Rc::make_mut`) and leaves the rest shared.
+impl<T> Node for Rc<T>
+where
+    T: Node + Debug + Clone,
+{
+    fn generate(visitor: &mut Visitor, depth: &mut usize, cur_depth: &mut usize) -> Self {
+        Rc::new(T::generate(visitor, depth, cur_depth))
+    }
+
+    /// Rc is transparent like Box, see the note on `Box::id`
+    fn id() -> Id {
+        T::id()
+    }
+
+    fn __len(&self) -> usize {
+        self.as_ref().__len()
+    }
+
+    /// the elements of the iterable we wrap, not the iterable itself: with T's id
+    /// the elements of a `Box<Vec<X>>` would have the id of the whole Vec
+    fn inner_id() -> Option<Id> {
+        T::inner_id()
+    }
+
+    fn cmps(&self, visitor: &mut Visitor, index: usize, val: (u64, u64)) {
+        self.as_ref().cmps(visitor, index, val);
+    }
+
+    fn nodes(&self, visitor: &mut Visitor, index: usize) {
+        self.as_ref().nodes(visitor, index);
+    }
+
+    fn observe(&self, visitor: &mut Visitor) {
+        self.as_ref().observe(visitor);
+    }
+
+    fn __walk(&self, walker: &mut dyn Walk, index: usize) {
+        self.as_ref().__walk(walker, index);
+    }
+
+    fn __header_len(&self) -> usize {
+        self.as_ref().__header_len()
+    }
+
+    fn __mutate(&mut self, ty: &mut MutationType, visitor: &mut Visitor, path: VecDeque<usize>) {
+        Rc::make_mut(self).__mutate(ty, visitor, path);
+    }
+    fn __describe(graph: &mut TypeGraph) -> Shape {
+        Shape::Transparent(graph.add::<T>())
+    }
+
+    fn __rust_expr(&self, out: &mut String) {
+        out.push_str("std::rc::Rc::new(");
+        self.as_ref().__rust_expr(out);
+        out.push(')');
     }
 }
 
@@ -417,7 +573,8 @@ where
     T: Node + Debug,
 {
     fn generate(visitor: &mut Visitor, depth: &mut usize, cur_depth: &mut usize) -> Self {
-        let choose_some = visitor.coinflip();
+        let choose_some =
+            *cur_depth < MAX_DEPTH && visitor.choose_unswarmed_variant(&Self::id(), &[0, 1]) == 1;
         if choose_some {
             Some(T::generate(visitor, depth, cur_depth))
         } else {
@@ -441,6 +598,9 @@ where
                 MutationType::GenerateReplace(ref mut bias) => {
                     *self = Self::generate(visitor, bias, &mut 0)
                 }
+                MutationType::Tweak(ref mut tweaked) => {
+                    *tweaked = visitor.tweak(self);
+                }
                 _ => {
                     unreachable!()
                 }
@@ -448,20 +608,6 @@ where
         }
     }
 
-    // TODO: for now we perform duplicate serialization cause the inner field is also serialized.
-    // and our parent will serialize us
-    fn serialized(&self) -> Option<Vec<(Vec<u8>, Id)>> {
-        if let Some(inner) = self {
-            let mut vector = vec![(serialize(&inner), T::id())];
-            if let Some(inner_nodes) = inner.serialized() {
-                vector.extend(inner_nodes)
-            }
-            Some(vector)
-        } else {
-            None
-        }
-    }
-
     fn nodes(&self, visitor: &mut Visitor, index: usize) {
         if let Some(inner) = self {
             let len = inner.__len();
@@ -484,6 +630,43 @@ where
             visitor.pop_field();
         }
     }
+
+    fn observe(&self, visitor: &mut Visitor) {
+        if let Some(inner) = self {
+            visitor.register_variant(Self::id(), 1, 2);
+            inner.observe(visitor);
+        } else {
+            visitor.register_variant(Self::id(), 0, 2);
+        }
+    }
+
+    /// u8 tag
+    fn __header_len(&self) -> usize {
+        1
+    }
+
+    fn __walk(&self, walker: &mut dyn Walk, index: usize) {
+        walker.enter(index, self);
+        if let Some(inner) = self {
+            inner.__walk(walker, 0);
+        }
+        walker.leave(self);
+    }
+
+    fn __describe(graph: &mut TypeGraph) -> Shape {
+        Shape::Option(graph.add::<T>())
+    }
+
+    fn __rust_expr(&self, out: &mut String) {
+        match self {
+            Some(inner) => {
+                out.push_str("Some(");
+                inner.__rust_expr(out);
+                out.push(')');
+            }
+            None => out.push_str("None"),
+        }
+    }
 }
 
 // This is very similar to the derive implementation fr Enum,
@@ -494,7 +677,7 @@ where
     E: Node + Debug,
 {
     fn generate(visitor: &mut Visitor, depth: &mut usize, cur_depth: &mut usize) -> Self {
-        let choose_ok = visitor.coinflip();
+        let choose_ok = visitor.choose_unswarmed_variant(&Self::id(), &[0, 1]) == 0;
         if choose_ok {
             Ok(T::generate(visitor, depth, cur_depth))
         } else {
@@ -528,6 +711,9 @@ where
                 MutationType::GenerateReplace(ref mut bias) => {
                     *self = Self::generate(visitor, bias, &mut 0);
                 }
+                MutationType::Tweak(ref mut tweaked) => {
+                    *tweaked = visitor.tweak(self);
+                }
                 _ => {
                     unreachable!()
                 }
@@ -535,24 +721,6 @@ where
         }
     }
 
-    fn serialized(&self) -> Option<Vec<(Vec<u8>, Id)>> {
-        if let Ok(inner) = self {
-            let mut vector = vec![(serialize(&inner), T::id())];
-            if let Some(inner_nodes) = inner.serialized() {
-                vector.extend(inner_nodes)
-            }
-            Some(vector)
-        } else if let Err(inner) = self {
-            let mut vector = vec![(serialize(&inner), T::id())];
-            if let Some(inner_nodes) = inner.serialized() {
-                vector.extend(inner_nodes)
-            }
-            Some(vector)
-        } else {
-            unreachable!("zKJv3wsE____")
-        }
-    }
-
     fn nodes(&self, visitor: &mut Visitor, index: usize) {
         visitor.register_field_stack(((index, NodeType::NonRecursive), Self::id()));
         if let Ok(inner) = self {
@@ -572,6 +740,62 @@ where
         }
         visitor.pop_field();
     }
+
+    fn observe(&self, visitor: &mut Visitor) {
+        match self {
+            Ok(inner) => {
+                visitor.register_variant(Self::id(), 0, 2);
+                inner.observe(visitor);
+            }
+            Err(inner) => {
+                visitor.register_variant(Self::id(), 1, 2);
+                inner.observe(visitor);
+            }
+        }
+    }
+
+    /// u32 variant tag
+    fn __header_len(&self) -> usize {
+        4
+    }
+
+    fn __walk(&self, walker: &mut dyn Walk, index: usize) {
+        walker.enter(index, self);
+        match self {
+            Ok(inner) => inner.__walk(walker, 0),
+            Err(inner) => inner.__walk(walker, 1),
+        }
+        walker.leave(self);
+    }
+
+    fn __describe(graph: &mut TypeGraph) -> Shape {
+        Shape::Enum(vec![
+            VariantShape {
+                name: "Ok",
+                recursive: false,
+                fields: vec![FieldShape::new::<T>(graph, "0", false)],
+            },
+            VariantShape {
+                name: "Err",
+                recursive: false,
+                fields: vec![FieldShape::new::<E>(graph, "0", false)],
+            },
+        ])
+    }
+
+    fn __rust_expr(&self, out: &mut String) {
+        match self {
+            Ok(inner) => {
+                out.push_str("Ok(");
+                inner.__rust_expr(out);
+            }
+            Err(inner) => {
+                out.push_str("Err(");
+                inner.__rust_expr(out);
+            }
+        }
+        out.push(')');
+    }
 }
 
 impl Node for std::string::String {
@@ -583,6 +807,14 @@ impl Node for std::string::String {
     fn __len(&self) -> usize {
         0
     }
+
+    fn observe(&self, visitor: &mut Visitor) {
+        visitor.register_string_hit(self);
+    }
+
+    fn __rust_expr(&self, out: &mut String) {
+        out.push_str(&format!("{:?}.to_string()", self));
+    }
 }
 
 macro_rules! tuple_impls {
@@ -611,6 +843,9 @@ macro_rules! tuple_impls {
                         MutationType::GenerateReplace(ref mut bias) => {
                             *self = Self::generate(visitor, bias, &mut 0);
                         },
+                        MutationType::Tweak(ref mut tweaked) => {
+                            *tweaked = visitor.tweak(self);
+                        },
             _  => {
                 unreachable!()
             }
@@ -624,17 +859,6 @@ macro_rules! tuple_impls {
                 visitor.pop_field();
                 })*
             }
-            fn serialized(&self) -> Option<Vec<(Vec<u8>, Id)>> {
-                let mut vector = Vec::new();
-                $(vector.push((serialize(&self.$id), $T::id()));)*
-                $({
-                    if let Some(inner) = self.$id.serialized() {
-                        vector.extend(inner)
-                    }
-                })*
-                    Some(vector)
-            }
-
             fn cmps(&self, visitor: &mut Visitor, index: usize, val: (u64, u64)) {
                 $({
                 visitor.register_field_stack(((($id, crate::NodeType::NonRecursive)), $T::id()));
@@ -642,6 +866,37 @@ macro_rules! tuple_impls {
                 visitor.pop_field();
                 })*
             }
+
+            fn observe(&self, visitor: &mut Visitor) {
+                $(self.$id.observe(visitor);)*
+            }
+
+            fn __walk(&self, walker: &mut dyn Walk, index: usize) {
+                walker.enter(index, self);
+                $(self.$id.__walk(walker, $id);)*
+                walker.leave(self);
+            }
+
+            fn __describe(graph: &mut TypeGraph) -> Shape {
+                Shape::Struct(vec![$(FieldShape::new::<$T>(graph, stringify!($id), false)),*])
+            }
+
+            fn __rust_expr(&self, out: &mut String) {
+                let mut count = 0;
+                out.push('(');
+                $(
+                    if count > 0 {
+                        out.push_str(", ");
+                    }
+                    self.$id.__rust_expr(out);
+                    count += 1;
+                )*
+                // `(a,)` is a tuple, `(a)` is not
+                if count == 1 {
+                    out.push(',');
+                }
+                out.push(')');
+            }
         }
     };
 }
@@ -671,6 +926,10 @@ macro_rules! impl_generate_simple {
                     v.register_cmp(serialize(&(val.1 as Self)));
                 };
             }
+            #[cfg(feature = "bincode")]
+            fn __rust_expr(&self, out: &mut String) {
+                out.push_str(&number_expr(format!("{:?}", self), stringify!($type)));
+            }
         }
     };
     // we don't do cmps for u8
@@ -683,6 +942,28 @@ macro_rules! impl_generate_simple {
     };
 }
 
+/// Debug is the literal, but for the floats that have none
+#[cfg(feature = "bincode")]
+fn number_expr(debug: String, ty: &str) -> String {
+    match debug.as_str() {
+        "NaN" => format!("{}::NAN", ty),
+        "inf" => format!("{}::INFINITY", ty),
+        "-inf" => format!("{}::NEG_INFINITY", ty),
+        _ => debug,
+    }
+}
+
+/// `a, b, c` for the elements of a Vec or array
+#[cfg(feature = "bincode")]
+pub(crate) fn rust_exprs<'a, T: Node + 'a>(elements: impl Iterator<Item = &'a T>, out: &mut String) {
+    for (i, element) in elements.enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        element.__rust_expr(out);
+    }
+}
+
 impl_generate_simple!(f32, 4);
 impl_generate_simple!(f64, 8);
 
@@ -754,3 +1035,12 @@ where
 {
     bincode::deserialize(data).expect("invariant; we must always be able to deserialize")
 }
+
+/// For bytes that did not come from us (eg. another fuzzer's queue)
+#[cfg(feature = "bincode")]
+pub fn try_deserialize<T>(data: &[u8]) -> Option<T>
+where
+    for<'a> T: serde::Deserialize<'a>,
+{
+    bincode::deserialize(data).ok()
+}
@@ -1 +1,20 @@
 // WHOEVER SAYS UTIL IS NOT A GOOD MODULE NAME; YOU KNOW WHHERE TO GO
+
+/// Stack we want left before recursing into a child
+const RED_ZONE: usize = 128 * 1024;
+/// Size of the stack segments we grow by
+const STACK_SEGMENT: usize = 4 * 1024 * 1024;
+
+/// Run `f`, on a new stack segment if we are about to run out.
+/// All recursive methods of derived nodes go through this, so deep trees (eg. after a lot of
+/// splicing) grow the stack instead of overflowing it. Use it in recursive renderers too.
+#[inline(always)]
+pub fn grow<R>(f: impl FnOnce() -> R) -> R {
+    stacker::maybe_grow(RED_ZONE, STACK_SEGMENT, f)
+}
+
+/// Run `f` on a stack of `size` bytes.
+/// For recursion we don't control (eg. serde), which can't grow the stack as it goes.
+pub fn with_stack<R>(size: usize, f: impl FnOnce() -> R) -> R {
+    stacker::grow(size, f)
+}
@@ -1,8 +1,14 @@
 use libafl_bolts::rands::{Rand, StdRand};
+use std::collections::HashMap;
 
-use crate::Id;
+use crate::{
+    mutate::{self, Mutate, Tweak},
+    tape::Tape,
+    Id, Node, NodeIndex,
+};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "bincode", derive(serde::Serialize, serde::Deserialize))]
 pub enum NodeType {
     ///  A normal node
     NonRecursive,
@@ -13,6 +19,13 @@ pub enum NodeType {
 }
 
 
+/// `cur_depth` at which generation stops recursing: enums pick a non-recursive variant
+/// and `Option`s are `None`, whatever the weights say.
+pub const MAX_DEPTH: usize = 100;
+
+/// Weight of a variant we never saw, so learned weights can't rule out the one that terminates.
+const MIN_WEIGHT: f64 = 1e-3;
+
 #[derive(Debug, Clone)]
 pub struct DepthInfo {
     /// if we should expand the nested items when serializing
@@ -23,6 +36,16 @@ pub struct DepthInfo {
     pub iterate: usize,
 }
 
+impl Default for DepthInfo {
+    fn default() -> Self {
+        Self {
+            expand: 1500,
+            generate: 3,
+            iterate: 5,
+        }
+    }
+}
+
 impl NodeType {
     pub fn iterable_size(&self) -> usize {
         if let Self::Iterable(size, _) = self {
@@ -43,10 +66,35 @@ impl NodeType {
 pub struct Visitor {
     depth: DepthInfo,
     strings: Vec<String>,
-    fields: Vec<Vec<((usize, NodeType), Id)>>,
-    fields_stack: Vec<((usize, NodeType), Id)>,
-    matching_cmps: Vec<(Vec<((usize, NodeType), Id)>, Vec<u8>)>,
+    /// every field registered since the last `node_index` / `nodes` / `cmps`
+    index: NodeIndex,
+    /// the entry of the field we are in and how deep it is
+    current: Option<u32>,
+    stack_len: usize,
+    /// (entry, serialized alternative)
+    matching_cmps: Vec<(Option<u32>, Vec<u8>)>,
     rng: StdRand,
+    /// how often we have observed each variant of a type in interesting inputs
+    variant_counts: HashMap<Id, Vec<usize>>,
+    /// learned weights used when choosing a variant, indexed by variant
+    variant_weights: HashMap<Id, Vec<f64>>,
+    /// how often we have observed each string in interesting inputs
+    string_counts: HashMap<String, usize>,
+    /// learned weights for the string pool, indexed like `strings`
+    string_weights: Vec<f64>,
+    /// 1.0 == always uniform, 0.0 == only what we learned
+    uniform_mix: f64,
+    /// chance that a variant is switched off for a swarm epoch (0.0 == no swarm testing)
+    swarm_probability: f64,
+    swarm_epoch: usize,
+    /// per epoch decisions, made lazily the first time we choose between the variants
+    swarm: HashMap<(Id, usize), bool>,
+    /// if set, generation choices are read from the tape instead of the rng
+    tape: Option<Tape>,
+    /// the strings a tape picks from: the pool we seeded, before dicts and cmplog added to it
+    tape_strings: usize,
+    /// the types with a `Mutate` impl, see `register_mutate`
+    tweaks: HashMap<Id, Tweak>,
 }
 
 pub const ERR_REMAIN_DEPTH: &str = "invariant; we should never be able to go over remaining_depth";
@@ -54,8 +102,26 @@ pub const ERR_OVERFLOW: &str = "invariant; you tree is too large? lol";
 
 impl Visitor {
     pub fn get_string(&mut self) -> String {
+        if self.tape.is_some() {
+            // a tape has to decode the same way for as long as we fuzz
+            let index = self.random_range(0, self.tape_strings - 1);
+            return self.strings[index].clone();
+        }
         let string_count = self.strings.len() - 1;
-        let index = self.random_range(0, string_count);
+        // the pool may have grown since we last reweighted
+        let index = if self.string_weights.len() == self.strings.len() {
+            let total = self.string_weights.iter().sum::<f64>();
+            let mut point = self.random_float() * total;
+            self.string_weights
+                .iter()
+                .position(|weight| {
+                    point -= weight;
+                    point < 0.0
+                })
+                .unwrap_or(string_count)
+        } else {
+            self.random_range(0, string_count)
+        };
         self.strings.get(index).expect("5hxil4dq____").clone()
     }
     // ADD STRINGS FROM AUTOTOKENS
@@ -64,6 +130,9 @@ impl Visitor {
     }
 
     pub fn generate_bytes(&mut self, amount: usize) -> Vec<u8> {
+        if let Some(tape) = self.tape.as_mut() {
+            return tape.next_bytes(amount);
+        }
         // TODO: possible to make more efficient?
         (0..amount)
             .map(|_| self.rng.next() as u8)
@@ -71,34 +140,225 @@ impl Visitor {
     }
 
     pub fn coinflip(&mut self) -> bool {
+        if let Some(tape) = self.tape.as_mut() {
+            // a zero byte must mean "no", so an exhausted tape stops recursing
+            return tape.next_byte() & 1 == 1;
+        }
         self.rng.coinflip(0.5)
     }
 
     pub fn coinflip_with_prob(&mut self, prob: f64) -> bool {
+        if let Some(tape) = self.tape.as_mut() {
+            // like `coinflip`, a zero byte means "no" (unless prob is 1.0)
+            return (tape.next_byte() as f64) >= (1.0 - prob) * 256.0;
+        }
         // NOTE: depth should always be > 0;
         // we make sure of this cause we don't call this func if not depth > 0
         self.rng.coinflip(prob)
     }
 
     pub fn random_range(&mut self, min: usize, max: usize) -> usize {
+        if let Some(tape) = self.tape.as_mut() {
+            let span = max - min;
+            if span == 0 {
+                return min;
+            }
+            // only read as many bytes as we need to cover the range
+            let amount = (usize::BITS - span.leading_zeros()).div_ceil(8) as usize;
+            return min + (tape.next_u64(amount) as usize) % (span + 1);
+        }
         self.rng.between(min, max)
     }
 
+    /// uniform float in [0, 1)
+    pub fn random_float(&mut self) -> f64 {
+        let random = if let Some(tape) = self.tape.as_mut() {
+            tape.next_u64(8)
+        } else {
+            self.rng.next()
+        };
+        (random >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Read all generation choices from `tape` until `take_tape` is called.
+    pub fn set_tape(&mut self, tape: Vec<u8>) {
+        self.tape = Some(Tape::new(tape));
+    }
+
+    /// Go back to the rng and hand out the tape.
+    pub fn take_tape(&mut self) -> Option<Tape> {
+        self.tape.take()
+    }
+
+    pub fn is_tape(&self) -> bool {
+        self.tape.is_some()
+    }
+
+    /// Pick one of `candidates` (variant indexes of the type `id`).
+    /// Uniform unless we have learned weights for the type.
+    /// Tapes ignore the learned weights and the swarm, both change as we fuzz.
+    pub fn choose_variant(&mut self, id: &Id, candidates: &[usize]) -> usize {
+        if self.swarm_probability > 0.0 && self.tape.is_none() {
+            let enabled = candidates
+                .iter()
+                .copied()
+                .filter(|candidate| self.swarm_enabled(id, *candidate))
+                .collect::<Vec<_>>();
+            // if the swarm switched everything off we have to ignore it, else we can't terminate
+            if !enabled.is_empty() && enabled.len() < candidates.len() {
+                return self.choose_weighted(id, &enabled);
+            }
+        }
+        self.choose_unswarmed_variant(id, candidates)
+    }
+
+    /// `choose_variant` without the swarm, for `Option` and `Result`: switching off `None`
+    /// (or whichever of `Ok`/`Err` doesn't recurse) leaves an `Option<Box<Self>>` nothing to stop on.
+    pub fn choose_unswarmed_variant(&mut self, id: &Id, candidates: &[usize]) -> usize {
+        if self.tape.is_some() {
+            return candidates[self.random_range(0, candidates.len() - 1)];
+        }
+        self.choose_weighted(id, candidates)
+    }
+
+    fn choose_weighted(&mut self, id: &Id, candidates: &[usize]) -> usize {
+        let total = self.variant_weights.get(id).map(|weights| {
+            candidates
+                .iter()
+                .map(|c| weight(weights, *c))
+                .sum::<f64>()
+        });
+        if let Some(total) = total.filter(|total| *total > 0.0) {
+            let mut point = self.random_float() * total;
+            let weights = self.variant_weights.get(id).expect("zXq3Lw0e____");
+            for candidate in candidates {
+                point -= weight(weights, *candidate);
+                if point < 0.0 {
+                    return *candidate;
+                }
+            }
+            return *candidates.last().expect("tM1oqPda____");
+        }
+        candidates[self.random_range(0, candidates.len() - 1)]
+    }
+
+    /// Count a variant seen in an interesting input.
+    pub fn register_variant(&mut self, id: Id, variant: usize, variant_count: usize) {
+        let counts = self
+            .variant_counts
+            .entry(id)
+            .or_insert_with(|| vec![0; variant_count]);
+        counts[variant] += 1;
+    }
+
+    /// Count a string seen in an interesting input.
+    pub fn register_string_hit(&mut self, string: &str) {
+        if let Some(count) = self.string_counts.get_mut(string) {
+            *count += 1;
+        } else {
+            self.string_counts.insert(string.to_string(), 1);
+        }
+    }
+
+    fn swarm_enabled(&mut self, id: &Id, variant: usize) -> bool {
+        if let Some(enabled) = self.swarm.get(&(id.clone(), variant)) {
+            return *enabled;
+        }
+        let enabled = !self.rng.coinflip(self.swarm_probability);
+        self.swarm.insert((id.clone(), variant), enabled);
+        enabled
+    }
+
+    /// Switch off each variant with probability `probability` per swarm epoch.
+    pub fn set_swarm_probability(&mut self, probability: f64) {
+        self.swarm_probability = probability.clamp(0.0, 1.0);
+        self.swarm.clear();
+    }
+
+    /// Forget the current swarm configuration; a new one is drawn as we generate.
+    pub fn new_swarm_epoch(&mut self) {
+        self.swarm_epoch += 1;
+        self.swarm.clear();
+    }
+
+    pub fn swarm_epoch(&self) -> usize {
+        self.swarm_epoch
+    }
+
+    pub fn is_swarm(&self) -> bool {
+        self.swarm_probability > 0.0
+    }
+
+    /// The variants switched off in the current epoch, of those drawn so far
+    /// (a variant is drawn the first time we generate its type).
+    pub fn swarm_disabled(&self) -> Vec<(Id, usize)> {
+        self.swarm
+            .iter()
+            .filter(|(_, enabled)| !**enabled)
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    pub fn set_uniform_mix(&mut self, uniform_mix: f64) {
+        self.uniform_mix = uniform_mix.clamp(0.0, 1.0);
+    }
+
+    /// Turn the observed counts into generation weights.
+    /// Every choice keeps at least `uniform_mix / n` so we never stop exploring.
+    pub fn reweight(&mut self) {
+        let mix = self.uniform_mix;
+        self.variant_weights = self
+            .variant_counts
+            .iter()
+            .map(|(id, counts)| (id.clone(), mix_weights(counts, mix)))
+            .collect();
+        let counts = self
+            .strings
+            .iter()
+            .map(|string| self.string_counts.get(string).copied().unwrap_or(0))
+            .collect::<Vec<_>>();
+        self.string_weights = mix_weights(&counts, mix);
+    }
+
+    /// Let `MutationType::Tweak` change the nodes of type `T` with `Mutate::mutate`.
+    /// Register the type itself, not a `Box<T>` around it.
+    pub fn register_mutate<T: Node + Mutate + 'static>(&mut self) {
+        self.tweaks.insert(T::id(), mutate::tweak::<T>);
+    }
+
+    pub fn can_tweak(&self, id: &Id) -> bool {
+        self.tweaks.contains_key(id)
+    }
+
+    pub fn has_tweaks(&self) -> bool {
+        !self.tweaks.is_empty()
+    }
+
+    /// `Mutate::mutate` `node` if its type is registered
+    pub fn tweak<T: Node + 'static>(&mut self, node: &mut T) -> bool {
+        match self.tweaks.get(&T::id()).copied() {
+            Some(tweak) => tweak(node, self),
+            None => false,
+        }
+    }
+
     pub fn register_field(&mut self, item: ((usize, NodeType), Id)) {
-        self.fields_stack.push(item);
-        self.fields.push(self.fields_stack.clone());
+        self.register_field_stack(item);
+        let entry = self.current.expect("Xq4nR7tB____");
+        self.index.mark_node(entry);
     }
 
     pub fn register_cmp(&mut self, data: Vec<u8>) {
-        self.matching_cmps.push((self.fields_stack.clone(), data));
+        self.matching_cmps.push((self.current, data));
     }
 
     pub fn register_field_stack(&mut self, item: ((usize, NodeType), Id)) {
-        self.fields_stack.push(item);
+        self.current = Some(self.index.push(self.current, item));
+        self.stack_len += 1;
     }
 
     pub fn expand(&mut self) -> bool {
-        self.fields_stack.len() < self.depth.expand
+        self.stack_len < self.depth.expand
     }
 
     pub fn get_depth() {
@@ -118,32 +378,57 @@ impl Visitor {
     } 
     
     pub fn pop_field(&mut self) {
-        self.fields_stack.pop();
+        if let Some(entry) = self.current {
+            self.current = self.index.parent(entry);
+            self.stack_len -= 1;
+        }
     }
 
     pub fn cmps(&mut self) -> Vec<(Vec<((usize, NodeType), Id)>, Vec<u8>)> {
-        let cmps = self.matching_cmps.clone();
-        self.matching_cmps = vec![];
-        self.fields = vec![];
-        self.fields_stack = vec![];
-        cmps
+        let matching_cmps = std::mem::take(&mut self.matching_cmps);
+        let index = self.node_index();
+        matching_cmps
+            .into_iter()
+            .map(|(entry, data)| {
+                let fields = entry.map(|entry| index.entry_fields(entry)).unwrap_or_default();
+                (fields, data)
+            })
+            .collect()
     }
 
+    /// The nodes registered by `Node::nodes`, resets the index.
+    pub fn node_index(&mut self) -> NodeIndex {
+        self.current = None;
+        self.stack_len = 0;
+        std::mem::take(&mut self.index)
+    }
+
+    /// The field stack of every registered node, prefer `node_index` which does not copy the stacks.
     pub fn nodes(&mut self) -> Vec<Vec<((usize, NodeType), Id)>> {
-        let fields = self.fields.clone();
-        self.fields = vec![];
-        self.fields_stack = vec![];
-        fields
+        let index = self.node_index();
+        (0..index.len()).map(|i| index.fields(i)).collect()
     }
 
     pub fn new(seed: u64, depth: DepthInfo) -> Self {
         let mut visitor = Self {
             depth,
-            fields: vec![],
-            fields_stack: vec![],
+            index: NodeIndex::default(),
+            current: None,
+            stack_len: 0,
             matching_cmps: vec![],
             strings: vec![],
             rng: StdRand::with_seed(seed),
+            variant_counts: HashMap::default(),
+            variant_weights: HashMap::default(),
+            string_counts: HashMap::default(),
+            string_weights: vec![],
+            uniform_mix: 1.0,
+            swarm_probability: 0.0,
+            swarm_epoch: 0,
+            swarm: HashMap::default(),
+            tape: None,
+            tape_strings: 0,
+            tweaks: HashMap::default(),
         };
         while visitor.strings.len() < 100 {
             let element_count = visitor.random_range(1, 10);
@@ -157,6 +442,30 @@ impl Visitor {
                 visitor.strings.push(string);
             }
         }
+        visitor.tape_strings = visitor.strings.len();
         return visitor;
     }
 }
+
+fn weight(weights: &[f64], variant: usize) -> f64 {
+    weights
+        .get(variant)
+        .copied()
+        .unwrap_or(0.0)
+        .max(MIN_WEIGHT)
+}
+
+fn mix_weights(counts: &[usize], mix: f64) -> Vec<f64> {
+    let total = counts.iter().sum::<usize>();
+    let uniform = 1.0 / counts.len() as f64;
+    counts
+        .iter()
+        .map(|count| {
+            if total == 0 {
+                uniform
+            } else {
+                mix * uniform + (1.0 - mix) * (*count as f64 / total as f64)
+            }
+        })
+        .collect()
+}
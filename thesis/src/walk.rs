@@ -0,0 +1,224 @@
+use std::any::Any;
+
+use crate::{serialize, Id, Node, NodeType};
+
+/// Object safe view of a node, so walkers don't need to be generic over every type in the tree.
+pub trait DynNode {
+    fn type_name(&self) -> &'static str;
+    fn node_id(&self) -> Id;
+    fn node_type(&self) -> NodeType;
+    fn as_any(&self) -> &dyn Any;
+    /// the serialized node, this is what we store in the chunks directory
+    fn bytes(&self) -> Vec<u8>;
+    /// length of `bytes` without serializing
+    fn size(&self) -> usize;
+    /// bytes the encoding writes in front of our children, see `Node::__header_len`
+    fn header_len(&self) -> usize;
+    fn debug(&self) -> String;
+}
+
+impl<T> DynNode for T
+where
+    T: Node,
+{
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<T>()
+    }
+
+    fn node_id(&self) -> Id {
+        T::id()
+    }
+
+    fn node_type(&self) -> NodeType {
+        let len = self.__len();
+        // not every iterable knows its inner type (eg. serde arrays)
+        if let (true, Some(inner_id)) = (len > 0, T::inner_id()) {
+            NodeType::Iterable(len.saturating_sub(1), inner_id)
+        } else if self.is_recursive() {
+            NodeType::Recursive
+        } else {
+            NodeType::NonRecursive
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn bytes(&self) -> Vec<u8> {
+        serialize(self)
+    }
+
+    fn size(&self) -> usize {
+        bincode::serialized_size(self).expect("invariant; we must always be able to serialize") as usize
+    }
+
+    fn header_len(&self) -> usize {
+        self.__header_len()
+    }
+
+    fn debug(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+/// Depth first traversal of a tree.
+/// `index` is the path segment of the node under its parent, the same one `__mutate` pops.
+/// Enum variants are path segments without a node of their own, they are reported with `variant`.
+pub trait Walk {
+    fn enter(&mut self, index: usize, node: &dyn DynNode);
+    fn leave(&mut self, node: &dyn DynNode);
+    fn variant(&mut self, index: usize, name: &'static str) {}
+    fn leave_variant(&mut self) {}
+}
+
+/// Walk `root`. The root is entered with index 0, but has no path segment.
+pub fn walk<T: Node>(root: &T, walker: &mut dyn Walk) {
+    root.__walk(walker, 0);
+}
+
+/// One line of a tree dump
+#[derive(Debug, Clone)]
+pub struct DumpLine {
+    pub depth: usize,
+    pub path: Vec<usize>,
+    pub type_name: &'static str,
+    pub variant: Option<&'static str>,
+    pub node_type: NodeType,
+    pub snippet: Option<String>,
+}
+
+impl std::fmt::Display for DumpLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let indent = "  ".repeat(self.depth);
+        let path = self
+            .path
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join(".");
+        write!(f, "{}{} [{}]", indent, short_type_name(self.type_name), path)?;
+        if let Some(variant) = self.variant {
+            write!(f, " ::{}", variant)?;
+        }
+        match &self.node_type {
+            NodeType::Iterable(size, _) => write!(f, " Iterable({})", size + 1)?,
+            NodeType::Recursive => write!(f, " Recursive")?,
+            NodeType::NonRecursive => {}
+        }
+        if let Some(snippet) = &self.snippet {
+            write!(f, " | {}", snippet)?;
+        }
+        Ok(())
+    }
+}
+
+/// Collects a `DumpLine` per node.
+/// `snippet` gets every node and may render it (eg. by downcasting to a type with Display).
+/// Leaves without a snippet show their Debug output.
+pub struct Dumper<'a> {
+    pub lines: Vec<DumpLine>,
+    path: Vec<usize>,
+    /// (line, has children) of the nodes we are in
+    stack: Vec<(usize, bool)>,
+    max_snippet: usize,
+    snippet: &'a mut dyn FnMut(&dyn Any) -> Option<String>,
+}
+
+impl<'a> Dumper<'a> {
+    pub fn new(max_snippet: usize, snippet: &'a mut dyn FnMut(&dyn Any) -> Option<String>) -> Self {
+        Self {
+            lines: vec![],
+            path: vec![],
+            stack: vec![],
+            max_snippet,
+            snippet,
+        }
+    }
+
+    fn trim(&self, snippet: String) -> String {
+        let snippet = snippet.escape_debug().to_string();
+        if snippet.chars().count() > self.max_snippet {
+            format!("{}...", snippet.chars().take(self.max_snippet).collect::<String>())
+        } else {
+            snippet
+        }
+    }
+}
+
+impl<'a> Walk for Dumper<'a> {
+    fn enter(&mut self, index: usize, node: &dyn DynNode) {
+        if let Some(parent) = self.stack.last_mut() {
+            parent.1 = true;
+            self.path.push(index);
+        }
+        let snippet = (self.snippet)(node.as_any()).map(|s| self.trim(s));
+        self.stack.push((self.lines.len(), false));
+        self.lines.push(DumpLine {
+            depth: self.stack.len() - 1,
+            path: self.path.clone(),
+            type_name: node.type_name(),
+            variant: None,
+            node_type: node.node_type(),
+            snippet,
+        });
+    }
+
+    fn leave(&mut self, node: &dyn DynNode) {
+        let (line, has_children) = self.stack.pop().expect("Hc8FZGeV____");
+        // we only know if we are a leaf once we leave
+        if !has_children && self.lines[line].snippet.is_none() {
+            self.lines[line].snippet = Some(self.trim(node.debug()));
+        }
+        if !self.stack.is_empty() {
+            self.path.pop();
+        }
+    }
+
+    fn variant(&mut self, index: usize, name: &'static str) {
+        self.path.push(index);
+        if let Some((line, _)) = self.stack.last() {
+            self.lines[*line].variant = Some(name);
+        }
+    }
+
+    fn leave_variant(&mut self) {
+        self.path.pop();
+    }
+}
+
+/// Pretty print `root`, one node per line, indented, with its path, type and `NodeType`.
+pub fn dump<T: Node>(
+    root: &T,
+    max_snippet: usize,
+    mut snippet: impl FnMut(&dyn Any) -> Option<String>,
+) -> String {
+    let mut dumper = Dumper::new(max_snippet, &mut snippet);
+    walk(root, &mut dumper);
+    dumper
+        .lines
+        .iter()
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+/// `alloc::vec::Vec<my_crate::ast::Statement>` -> `Vec<Statement>`
+pub fn short_type_name(name: &str) -> String {
+    let mut short = String::with_capacity(name.len());
+    let mut segment = String::new();
+    let mut chars = name.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == ':' && chars.peek() == Some(&':') {
+            chars.next();
+            segment.clear();
+        } else if c.is_alphanumeric() || c == '_' {
+            segment.push(c);
+        } else {
+            short.push_str(&segment);
+            segment.clear();
+            short.push(c);
+        }
+    }
+    short.push_str(&segment);
+    short
+}
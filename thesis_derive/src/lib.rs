@@ -4,42 +4,60 @@
 * of args so with IDs, we need to find some sort of compromise
 */
 extern crate proc_macro2;
-use proc_macro::TokenStream;
+mod render;
+
 use quote::quote;
-use syn::{spanned::Spanned, token::Comma, *};
+use syn::{punctuated::Punctuated, spanned::Spanned, token::Comma, *};
 
-#[proc_macro_derive(Grammar, attributes(literal, recursive))]
+#[proc_macro_derive(Grammar, attributes(literal, recursive, generate_with, thesis))]
 pub fn my_derive_proc_macro(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let parsed = syn::parse_macro_input!(input as syn::DeriveInput);
+    derive_grammar(parsed)
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
+}
+
+/// Target bytes for binary formats, with fields computed when rendering, see `thesis::render`
+#[proc_macro_derive(Render, attributes(len_of, count_of, offset_of, checksum, be, le))]
+pub fn derive_render(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let parsed = syn::parse_macro_input!(input as syn::DeriveInput);
+    render::derive_render(parsed)
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
+}
+
+fn derive_grammar(parsed: DeriveInput) -> Result<proc_macro2::TokenStream> {
+    reject_attr(&parsed.attrs, "recursive")?;
+    reject_attr(&parsed.attrs, "literal")?;
+    // a type with its own generator is generated by it, but mutated like any other
+    let generate_with = parse_generate_with(&parsed.attrs)?;
+    let remote = parse_remote(&parsed.attrs)?;
+    parse_serde(&parsed.attrs, SERDE_CONTAINER)?;
     let root_name = parsed.ident;
     let expanded = match parsed.data {
         Data::Struct(ref data) => {
-            let nodes = get_nodes(&data.fields)
-                .expect("Structs cannot have no nodes according to borsh!");
+            let nodes = get_nodes(&data.fields).ok_or_else(|| {
+                Error::new(
+                    root_name.span(),
+                    "Grammar can't be derived for unit structs, they have no nodes",
+                )
+            })?;
             let is_named = matches!(data.fields, syn::Fields::Named(_));
-            let parsed = parse_nodes(nodes);
-            let generate = construct_generate_function_struct(&parsed, is_named);
-
-            let serialized_ids = parsed.iter().map(|field| {
-                let name = field.get_name(is_named);
-                let ty = &field.ty;
-                quote! {
-                    let len = self.#name.__len();
-                    if len == 0 {
-                        vector.push((::thesis::serialize(&self.#name), <#ty>::id()));
-                    }
-                }
-            });
-            let serialized_recursive = parsed.iter().map(|field| {
-                let name = field.get_name(is_named);
-                quote! {
-                    if let Some(nodes) = self.#name.serialized() {
-                        vector.extend(nodes);
-                    }
-                }
-            });
+            let parsed = parse_nodes(nodes)?;
+            let generate = match &generate_with {
+                Some(path) => quote! { #path(v) },
+                None => construct_generate_function_struct(&parsed, is_named),
+            };
+            let encoded = parsed
+                .iter()
+                .filter(|field| field.skip.is_none())
+                .collect::<Vec<_>>();
+            let nodes = parsed
+                .iter()
+                .filter(|field| field.is_node())
+                .collect::<Vec<_>>();
 
-            let register_field = parsed.iter().map(|field| {
+            let register_field = nodes.iter().map(|field| {
                 let id = &field.id;
                 let ty = &field.ty;
                 let name = field.get_name(is_named);
@@ -54,7 +72,7 @@ pub fn my_derive_proc_macro(input: proc_macro::TokenStream) -> proc_macro::Token
                     v.pop_field();
                 }
             });
-            let register_cmps = parsed.iter().map(|field| {
+            let register_cmps = nodes.iter().map(|field| {
                 let id = &field.id;
                 let ty = &field.ty;
                 let name = field.get_name(is_named);
@@ -72,13 +90,47 @@ pub fn my_derive_proc_macro(input: proc_macro::TokenStream) -> proc_macro::Token
                 }
             });
 
-            let inner_mutate = parsed.iter().map(|field| {
+            let inner_mutate = nodes.iter().map(|field| {
+                let name = field.get_name(is_named);
+                field.mutate_arm(quote! { self.#name })
+            });
+
+            let observe = encoded.iter().map(|field| {
+                let name = field.get_name(is_named);
+                quote! {
+                    self.#name.observe(v);
+                }
+            });
+
+            let walk = encoded.iter().map(|field| {
                 let id = &field.id;
                 let name = field.get_name(is_named);
                 quote! {
-                    #id => {
-                        self.#name.__mutate(ty, visitor, path);
-                    },
+                    self.#name.__walk(walker, #id);
+                }
+            });
+
+            let rust_expr = rust_expr(root_name.to_string(), &data.fields, &parsed, |field| {
+                let name = field.get_name(is_named);
+                quote! { &self.#name }
+            });
+
+            let describe = encoded.iter().map(|field| field.describe(is_named));
+            let describe = match &generate_with {
+                Some(_) => quote! { ::thesis::lint::Shape::Leaf },
+                None => quote! { ::thesis::lint::Shape::Struct(vec![#(#describe),*]) },
+            };
+            let walk_describe = bincode_only(quote! {
+                fn __walk(&self, walker: &mut dyn ::thesis::walk::Walk, index: usize) {
+                    ::thesis::grow(|| {
+                    walker.enter(index, self);
+                    #(#walk)*
+                    walker.leave(self);
+                    })
+                }
+
+                fn __describe(graph: &mut ::thesis::lint::TypeGraph) -> ::thesis::lint::Shape {
+                    #describe
                 }
             });
 
@@ -86,27 +138,41 @@ pub fn my_derive_proc_macro(input: proc_macro::TokenStream) -> proc_macro::Token
             let node_impl = quote! {
                 impl ::thesis::Node for #root_name {
                     fn generate(v: &mut thesis::Visitor, depth: &mut usize, cur_depth: &mut usize) -> Self {
+                        ::thesis::grow(|| {
                         *cur_depth += 1usize;
                         #generate
+                        })
                     }
 
 
                     fn nodes(&self, v: &mut ::thesis::Visitor, index: usize) {
+                        ::thesis::grow(|| {
                         #(#register_field)*;
+                        })
                     }
 
                     fn cmps(&self, v: &mut ::thesis::Visitor, index: usize, val: (u64, u64)) {
+                        ::thesis::grow(|| {
                         #(#register_cmps)*
+                        })
                     }
 
-                    fn serialized(&self) -> Option<Vec<(Vec<u8>, thesis::tree::Id)>> {
-                        let mut vector = ::std::vec![];
-                        #(#serialized_ids);*
-                        #(#serialized_recursive);*
-                        Some(vector)
+                    fn observe(&self, v: &mut ::thesis::Visitor) {
+                        ::thesis::grow(|| {
+                        #(#observe)*
+                        })
+                    }
+
+                    #walk_describe
+
+                    fn __rust_expr(&self, out: &mut String) {
+                        ::thesis::grow(|| {
+                        #rust_expr
+                        })
                     }
 
                     fn __mutate(&mut self, ty: &mut thesis::MutationType, visitor: &mut thesis::Visitor, mut path: std::collections::VecDeque<usize>) {
+                        ::thesis::grow(|| {
                         if let Some(popped) = path.pop_front() {
                             match popped {
                                 #(#inner_mutate)*
@@ -122,13 +188,17 @@ pub fn my_derive_proc_macro(input: proc_macro::TokenStream) -> proc_macro::Token
                                 thesis::MutationType::GenerateReplace(ref mut bias) => {
                                     *self = Self::generate(visitor, bias, &mut 0);
                                 }
+                                thesis::MutationType::Tweak(ref mut tweaked) => {
+                                    *tweaked = visitor.tweak(self);
+                                }
                                 _  => {
                                     unreachable!()
                                 }
                             }
                         }
+                        })
                     }
-                };
+                }
             };
 
             quote! {
@@ -140,24 +210,23 @@ pub fn my_derive_proc_macro(input: proc_macro::TokenStream) -> proc_macro::Token
             let mut min_size = vec![];
             let mut fn_nodes = vec![];
             let mut inner_mutate = vec![];
-            let mut serialized = vec![];
             let mut fn_cmps = vec![];
+            let mut observe = vec![];
+            let mut walk = vec![];
+            let mut describe = vec![];
+            let mut rust_exprs = vec![];
+            let variant_count = data.variants.len();
 
             let mut recursive_variants = vec![];
             let mut non_recursive_variants = vec![];
             let mut are_we_recursive = vec![];
             for (i, variant) in data.variants.iter().enumerate() {
                 let variant_name = &variant.ident;
-                let attrs = &variant.attrs;
-                let mut is_recursive = false;
-                for attr in attrs {
-                    if let Meta::Path(ref list) = attr.meta {
-                        // make sure the attribute we are considering is ours.
-                        if list.segments.first().unwrap().ident == "recursive" {
-                            is_recursive = true;
-                        }
-                    }
-                }
+                reject_attr(&variant.attrs, "literal")?;
+                reject_attr(&variant.attrs, "generate_with")?;
+                reject_attr(&variant.attrs, "thesis")?;
+                let is_recursive = is_recursive(variant)?;
+                parse_serde(&variant.attrs, SERDE_VARIANT)?;
                 let nodes = get_nodes(&variant.fields);
                 let is_named = matches!(variant.fields, syn::Fields::Named(_));
                 if is_recursive {
@@ -184,9 +253,27 @@ pub fn my_derive_proc_macro(input: proc_macro::TokenStream) -> proc_macro::Token
                 min_size.push(variant_min_size);
 
                 let nodes = match nodes {
-                    Some(nodes) => parse_nodes(nodes),
+                    Some(nodes) => parse_nodes(nodes)?,
                     None => vec![],
                 };
+                // skipped fields aren't encoded, so they are no nodes (and need not be a Node)
+                let encoded = nodes
+                    .iter()
+                    .filter(|field| field.skip.is_none())
+                    .collect::<Vec<_>>();
+                // fields computed when rendering are encoded, but we don't mutate them
+                let mutable = nodes
+                    .iter()
+                    .filter(|field| field.is_node())
+                    .collect::<Vec<_>>();
+                let describe_fields = encoded.iter().map(|field| field.describe(is_named));
+                describe.push(quote! {
+                    ::thesis::lint::VariantShape {
+                        name: stringify!(#variant_name),
+                        recursive: #is_recursive,
+                        fields: vec![#(#describe_fields),*],
+                    }
+                });
                 are_we_recursive.push(if !nodes.is_empty() {
                     if is_named {
                         quote! {#root_name::#variant_name{..} => #is_recursive}
@@ -207,7 +294,7 @@ pub fn my_derive_proc_macro(input: proc_macro::TokenStream) -> proc_macro::Token
                 });
 
                 let field_fn = if !nodes.is_empty() {
-                    let variant_nodes_register = nodes.iter().map(|field| {
+                    let variant_nodes_register = mutable.iter().map(|field| {
                         let name = &field.name;
                         let ty = &field.ty;
                         let id = &field.id;
@@ -224,10 +311,9 @@ pub fn my_derive_proc_macro(input: proc_macro::TokenStream) -> proc_macro::Token
                             v.pop_field();
                         }
                     });
-                    let field_names = nodes.iter().map(|field| {
-                        let name = &field.name;
-                        quote! {#name}
-                    });
+                    let field_names = nodes
+                        .iter()
+                        .map(|field| field.binding(is_named, field.is_node()));
                     // Note: won't work with enums with generics
                     // cause it won't be Self
                     let match_arm = if is_named {
@@ -254,7 +340,7 @@ pub fn my_derive_proc_macro(input: proc_macro::TokenStream) -> proc_macro::Token
                 fn_nodes.push(field_fn);
 
                 let fn_cmp = if !nodes.is_empty() {
-                    let variant_nodes_cmp = nodes.iter().map(|field| {
+                    let variant_nodes_cmp = mutable.iter().map(|field| {
                         let name = &field.name;
                         let ty = &field.ty;
                         let id = &field.id;
@@ -269,10 +355,9 @@ pub fn my_derive_proc_macro(input: proc_macro::TokenStream) -> proc_macro::Token
                             v.pop_field();
                         }
                     });
-                    let field_names = nodes.iter().map(|field| {
-                        let name = &field.name;
-                        quote! {#name}
-                    });
+                    let field_names = nodes
+                        .iter()
+                        .map(|field| field.binding(is_named, field.is_node()));
                     // Note: won't work with enums with generics
                     // cause it won't be Self
                     let match_arm = if is_named {
@@ -295,18 +380,12 @@ pub fn my_derive_proc_macro(input: proc_macro::TokenStream) -> proc_macro::Token
 
                 fn_cmps.push(fn_cmp);
                 let inner_mutate_variant = if !nodes.is_empty() {
-                    let field_names = nodes.iter().map(|field| {
-                        let name = &field.name;
-                        quote! {#name}
-                    });
-                    let variant_nodes_mutate = nodes.iter().map(|field| {
+                    let field_names = nodes
+                        .iter()
+                        .map(|field| field.binding(is_named, field.is_node()));
+                    let variant_nodes_mutate = mutable.iter().map(|field| {
                         let name = &field.name;
-                        let id = &field.id;
-                        quote! {
-                            #id => {
-                                #name.__mutate(ty, visitor, path);
-                            },
-                        }
+                        field.mutate_arm(quote! { (*#name) })
                     });
 
                     let match_arm = if is_named {
@@ -339,107 +418,176 @@ pub fn my_derive_proc_macro(input: proc_macro::TokenStream) -> proc_macro::Token
 
                 inner_mutate.push(inner_mutate_variant);
 
-                if !nodes.is_empty() {
-                    let field_names = nodes.iter().map(|field| {
+                let variant_rust_expr = rust_expr(
+                    format!("{}::{}", root_name, variant_name),
+                    &variant.fields,
+                    &nodes,
+                    |field| {
                         let name = &field.name;
-                        quote! {#name}
-                    });
+                        quote! { #name }
+                    },
+                );
+                if !nodes.is_empty() {
+                    let field_names = nodes
+                        .iter()
+                        .map(|field| field.binding(is_named, field.skip.is_none()));
                     let match_arm = if is_named {
                         quote! {Self::#variant_name{#(#field_names),*} => }
                     } else {
                         quote! {Self::#variant_name(#(#field_names),*) => }
                     };
-                    let serialized_nodes = nodes.iter().map(|field| {
+                    let observe_nodes = encoded.iter().map(|field| {
                         let name = &field.name;
-                        let ty = &field.ty;
                         quote! {
-                            let len = #name.__len();
-                            if len == 0 {
-                                vector.push((::thesis::serialize(&#name), <#ty>::id()));
-                            }
-                            if let Some(nodes) = #name.serialized() {
-                                vector.extend(nodes);
-                            }
+                            #name.observe(v);
+                        }
+                    });
+                    observe.push(quote! {
+                        #match_arm {
+                            v.register_variant(Self::id(), #i, #variant_count);
+                            #(#observe_nodes)*
+                        }
+                    });
+                    let walk_nodes = encoded.iter().map(|field| {
+                        let name = &field.name;
+                        let id = &field.id;
+                        quote! {
+                            #name.__walk(walker, #id);
+                        }
+                    });
+                    walk.push(quote! {
+                        #match_arm {
+                            walker.variant(#i, stringify!(#variant_name));
+                            #(#walk_nodes)*
+                            walker.leave_variant();
+                        }
+                    });
+                    rust_exprs.push(quote! {
+                        #match_arm {
+                            #variant_rust_expr
                         }
                     });
-                    let serialized_variant = quote! {
-                    #match_arm {
-                        #(#serialized_nodes)*
-                    }
-                    };
-                    serialized.push(serialized_variant);
                 } else {
-                    serialized.push(quote! {
+                    observe.push(quote! {
                         Self::#variant_name{} => {
+                            v.register_variant(Self::id(), #i, #variant_count);
                         }
-                    })
+                    });
+                    walk.push(quote! {
+                        Self::#variant_name{} => {
+                            walker.variant(#i, stringify!(#variant_name));
+                            walker.leave_variant();
+                        }
+                    });
+                    rust_exprs.push(quote! {
+                        Self::#variant_name{} => {
+                            #variant_rust_expr
+                        }
+                    });
                 }
+
             }
-            if non_recursive_variants.is_empty() {
-                panic!(
-                    "{:?} has no non-recursive variants. This is a huge problem!",
-                    root_name
-                );
+            if non_recursive_variants.is_empty() && generate_with.is_none() {
+                return Err(Error::new(
+                    root_name.span(),
+                    format!(
+                        "{} has no non-recursive variants, so generating it never ends. \
+                         Add a variant without #[recursive]",
+                        root_name
+                    ),
+                ));
             }
             let variant_id_calculation = if !recursive_variants.is_empty() {
-                let recursive_variant_count = recursive_variants
-                    .len()
-                    .checked_sub(1)
-                    .expect("nFeGkMPw____");
-                let non_recursive_variant_count = non_recursive_variants
-                    .len()
-                    .checked_sub(1)
-                    .expect("we must have atleast 1 non-recursive variant");
                 quote! {
                     let r_variants = [#(#recursive_variants)*];
                     let nr_variants = [#(#non_recursive_variants)*];
-                    let choose_recursive = *depth > 0usize && v.coinflip() && *cur_depth < 100;
+                    let choose_recursive = *depth > 0usize && v.coinflip() && *cur_depth < ::thesis::MAX_DEPTH;
                     let variant_id = if choose_recursive {
-                            let index = v.random_range(0usize, #recursive_variant_count);
                             *depth = depth.checked_sub(1).expect("XVldNrja____");
-                            r_variants[index]
+                            v.choose_variant(&Self::id(), &r_variants)
                     } else {
-                        let index = v.random_range(0usize, #non_recursive_variant_count);
-                        nr_variants[index]
+                        v.choose_variant(&Self::id(), &nr_variants)
                     };
                 }
             } else {
-                let variant_count = non_recursive_variants
-                    .len()
-                    .checked_sub(1)
-                    .expect("we must have atleast 1 non-recursive variant");
                 quote! {
-                        let variant_id = v.random_range(0usize, #variant_count);
+                        let variant_id = v.choose_variant(&Self::id(), &[#(#non_recursive_variants)*]);
                 }
             };
+            let generate = match &generate_with {
+                Some(path) => quote! { #path(v) },
+                None => quote! {
+                    #variant_id_calculation
+                    match variant_id {
+                         #(#generate,)*
+                        _ => unreachable!()
+                    }
+                },
+            };
+            let describe = match &generate_with {
+                Some(_) => quote! { ::thesis::lint::Shape::Leaf },
+                None => quote! { ::thesis::lint::Shape::Enum(vec![#(#describe),*]) },
+            };
+            let walk_describe = bincode_only(quote! {
+                fn __walk(&self, walker: &mut dyn ::thesis::walk::Walk, index: usize) {
+                    ::thesis::grow(|| {
+                    walker.enter(index, self);
+                    match self {
+                        #(#walk,)*
+                    }
+                    walker.leave(self);
+                    })
+                }
+
+                fn __describe(graph: &mut ::thesis::lint::TypeGraph) -> ::thesis::lint::Shape {
+                    #describe
+                }
+            });
             // Generate the Node trait implementation for the Enum
             // TODO: can optimize this if the enum has only two nodes like (Result)
             let node_impl = quote! {
                 impl ::thesis::Node for #root_name {
                     fn generate(v: &mut ::thesis::Visitor, depth: &mut usize, cur_depth: &mut usize) -> Self {
+                        ::thesis::grow(|| {
                         *cur_depth += 1usize;
-                        #variant_id_calculation
-                        match variant_id {
-                             #(#generate,)*
-                            _ => unreachable!()
-                        }
+                        #generate
+                        })
                     }
 
 
                     fn nodes(&self, v: &mut ::thesis::Visitor, index: usize) {
+                        ::thesis::grow(|| {
                         #(#fn_nodes)*;
+                        })
                     }
 
                     fn cmps(&self, v: &mut ::thesis::Visitor, index: usize, val: (u64, u64)) {
+                        ::thesis::grow(|| {
                         #(#fn_cmps)*;
+                        })
                     }
 
-                    fn serialized(&self) -> Option<Vec<(Vec<u8>, thesis::tree::Id)>> {
-                        let mut vector = ::std::vec![];
+                    fn observe(&self, v: &mut ::thesis::Visitor) {
+                        ::thesis::grow(|| {
                         match self {
-                             #(#serialized,)*
+                            #(#observe,)*
                         }
-                        Some(vector)
+                        })
+                    }
+
+                    #walk_describe
+
+                    fn __rust_expr(&self, out: &mut String) {
+                        ::thesis::grow(|| {
+                        match self {
+                            #(#rust_exprs)*
+                        }
+                        })
+                    }
+
+                    /// u32 variant tag
+                    fn __header_len(&self) -> usize {
+                        4
                     }
 
                     fn is_recursive(&self) -> bool {
@@ -449,6 +597,7 @@ pub fn my_derive_proc_macro(input: proc_macro::TokenStream) -> proc_macro::Token
                     }
 
                     fn __mutate(&mut self, ty: &mut thesis::MutationType, visitor: &mut thesis::Visitor, mut path: std::collections::VecDeque<usize>) {
+                        ::thesis::grow(|| {
                         if let Some(popped) = path.pop_front() {
                             match popped {
                             #(#inner_mutate)*
@@ -463,6 +612,9 @@ pub fn my_derive_proc_macro(input: proc_macro::TokenStream) -> proc_macro::Token
                                 thesis::MutationType::GenerateReplace(ref mut bias) => {
                                     *self = Self::generate(visitor, bias, &mut 0);
                                 }
+                                thesis::MutationType::Tweak(ref mut tweaked) => {
+                                    *tweaked = visitor.tweak(self);
+                                }
                                 thesis::MutationType::RecursiveReplace => {
                                     if self.is_recursive() {
                                         // 0 depth == always non-recursive
@@ -474,6 +626,7 @@ pub fn my_derive_proc_macro(input: proc_macro::TokenStream) -> proc_macro::Token
                                 }
                             }
                         }
+                        })
                     }
                 }
             };
@@ -481,12 +634,196 @@ pub fn my_derive_proc_macro(input: proc_macro::TokenStream) -> proc_macro::Token
                 #node_impl
             }
         }
-        Data::Union(..) => todo!(),
+        Data::Union(ref data) => {
+            return Err(Error::new(
+                data.union_token.span(),
+                "Grammar can't be derived for unions, use an enum",
+            ));
+        }
+    };
+    // bytes are read as a decision tape, so every byte string is a valid Self
+    #[cfg(feature = "arbitrary")]
+    let expanded = quote! {
+        #expanded
+
+        impl<'a> ::thesis::arbitrary::Arbitrary<'a> for #root_name {
+            fn arbitrary(u: &mut ::thesis::arbitrary::Unstructured<'a>) -> ::thesis::arbitrary::Result<Self> {
+                ::thesis::tape::arbitrary(u)
+            }
+
+            fn arbitrary_take_rest(u: ::thesis::arbitrary::Unstructured<'a>) -> ::thesis::arbitrary::Result<Self> {
+                ::thesis::tape::arbitrary_take_rest(u)
+            }
+        }
+    };
+    let expanded = match remote {
+        Some(remote) => {
+            let remote_impl = derive_remote(&root_name, &remote, &parsed.data)?;
+            quote! {
+                #expanded
+                #remote_impl
+            }
+        }
+        None => expanded,
+    };
+    Ok(expanded)
+}
+
+/// `thesis::walk` and `thesis::lint` only exist with thesis' `bincode` feature, which turns ours on
+#[cfg(feature = "bincode")]
+fn bincode_only(items: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    items
+}
+
+#[cfg(not(feature = "bincode"))]
+fn bincode_only(_: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    proc_macro2::TokenStream::new()
+}
+
+/// `#[thesis(remote = "other::Type")]`, the foreign type we mirror
+fn parse_remote(attrs: &[Attribute]) -> Result<Option<Path>> {
+    let mut remote = None;
+    for attr in attrs {
+        if !attr.path().is_ident("thesis") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("remote") {
+                if remote.is_some() {
+                    return Err(meta.error("duplicate remote"));
+                }
+                let path: LitStr = meta.value()?.parse()?;
+                remote = Some(path.parse::<Path>()?);
+                Ok(())
+            } else {
+                Err(meta.error("unknown thesis attribute, expected #[thesis(remote = \"other::Type\")]"))
+            }
+        })?;
+    }
+    Ok(remote)
+}
+
+/// The pattern binding the fields, and `to` built from them converted with `Remote::method`
+fn convert_fields(
+    fields: &Vec<GrammarField>,
+    is_named: bool,
+    to: proc_macro2::TokenStream,
+    method: proc_macro2::TokenStream,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    let names = fields.iter().map(|field| &field.name).collect::<Vec<_>>();
+    if fields.is_empty() {
+        // unit variants match and build with `{}` like the rest of the derive
+        (quote! { {} }, quote! { #to {} })
+    } else if is_named {
+        (
+            quote! { { #(#names),* } },
+            quote! { #to { #(#names: ::thesis::remote::Remote::#method(#names)),* } },
+        )
+    } else {
+        (
+            quote! { ( #(#names),* ) },
+            quote! { #to ( #(::thesis::remote::Remote::#method(#names)),* ) },
+        )
+    }
+}
+
+/// `thesis::remote::Remote` and `From` both ways between the mirror and the remote type.
+/// Fields convert with `Remote` too, so they can be mirrors (or Vecs, Boxes, ... of mirrors).
+fn derive_remote(root_name: &Ident, remote: &Path, data: &Data) -> Result<proc_macro2::TokenStream> {
+    let (into_remote, from_remote) = match data {
+        Data::Struct(data) => {
+            let fields = match get_nodes(&data.fields) {
+                Some(fields) => parse_nodes(fields)?,
+                None => vec![],
+            };
+            let is_named = matches!(data.fields, syn::Fields::Named(_));
+            let (pattern, into) =
+                convert_fields(&fields, is_named, quote! {#remote}, quote! {into_remote});
+            let (_, from) = convert_fields(&fields, is_named, quote! {Self}, quote! {from_remote});
+            (
+                quote! {
+                    let Self #pattern = self;
+                    #into
+                },
+                quote! {
+                    let #remote #pattern = remote;
+                    #from
+                },
+            )
+        }
+        Data::Enum(data) => {
+            let mut into_arms = vec![];
+            let mut from_arms = vec![];
+            for variant in &data.variants {
+                let variant_name = &variant.ident;
+                let fields = match get_nodes(&variant.fields) {
+                    Some(fields) => parse_nodes(fields)?,
+                    None => vec![],
+                };
+                let is_named = matches!(variant.fields, syn::Fields::Named(_));
+                let (pattern, into) = convert_fields(
+                    &fields,
+                    is_named,
+                    quote! {#remote::#variant_name},
+                    quote! {into_remote},
+                );
+                let (_, from) = convert_fields(
+                    &fields,
+                    is_named,
+                    quote! {Self::#variant_name},
+                    quote! {from_remote},
+                );
+                into_arms.push(quote! { Self::#variant_name #pattern => #into, });
+                from_arms.push(quote! { #remote::#variant_name #pattern => #from, });
+            }
+            (
+                quote! {
+                    match self {
+                        #(#into_arms)*
+                    }
+                },
+                quote! {
+                    match remote {
+                        #(#from_arms)*
+                    }
+                },
+            )
+        }
+        Data::Union(data) => {
+            return Err(Error::new(
+                data.union_token.span(),
+                "Grammar can't be derived for unions, use an enum",
+            ))
+        }
     };
-    TokenStream::from(expanded)
+    Ok(quote! {
+        impl ::thesis::remote::Remote for #root_name {
+            type Remote = #remote;
+
+            fn into_remote(self) -> #remote {
+                #into_remote
+            }
+
+            fn from_remote(remote: #remote) -> Self {
+                #from_remote
+            }
+        }
+
+        impl ::std::convert::From<#root_name> for #remote {
+            fn from(mirror: #root_name) -> Self {
+                ::thesis::remote::Remote::into_remote(mirror)
+            }
+        }
+
+        impl ::std::convert::From<#remote> for #root_name {
+            fn from(remote: #remote) -> Self {
+                ::thesis::remote::Remote::from_remote(remote)
+            }
+        }
+    })
 }
 
-fn parse_nodes(fields: &syn::punctuated::Punctuated<syn::Field, Comma>) -> Vec<GrammarField> {
+fn parse_nodes(fields: &syn::punctuated::Punctuated<syn::Field, Comma>) -> Result<Vec<GrammarField>> {
     fields
         .iter()
         .enumerate()
@@ -496,14 +833,229 @@ fn parse_nodes(fields: &syn::punctuated::Punctuated<syn::Field, Comma>) -> Vec<G
                 Some(ident) => ident,
                 None => Ident::new(&format!("_{}", id), field.span()),
             };
-            GrammarField {
+            reject_attr(&field.attrs, "recursive")?;
+            reject_attr(&field.attrs, "thesis")?;
+            let literals = parse_literals(field)?;
+            let generate_with = parse_generate_with(&field.attrs)?;
+            if literals.is_some() && generate_with.is_some() {
+                return Err(Error::new_spanned(
+                    field,
+                    "a field can't have both #[literal] and #[generate_with]",
+                ));
+            }
+            let (skip, default) = parse_serde(&field.attrs, SERDE_FIELD)?;
+            if skip && (literals.is_some() || generate_with.is_some()) {
+                return Err(Error::new_spanned(
+                    field,
+                    "a #[serde(skip)] field is never generated, it can't have #[literal] or #[generate_with]",
+                ));
+            }
+            let skip = skip.then(|| match default {
+                Some(path) => quote! { #path() },
+                None => quote! { ::std::default::Default::default() },
+            });
+            let computed = render::is_computed(field);
+            if computed && (literals.is_some() || generate_with.is_some() || skip.is_some()) {
+                return Err(Error::new_spanned(
+                    field,
+                    "a field computed when rendering isn't generated, it can't have #[literal], \
+                     #[generate_with] or #[serde(skip)]",
+                ));
+            }
+            Ok(GrammarField {
+                literals,
+                generate_with,
+                skip,
+                computed,
                 name,
                 ty: ty.clone(),
                 id,
-                attrs: field.attrs.clone(),
-            }
+            })
         })
-        .collect::<Vec<_>>()
+        .collect()
+}
+
+/// `#[recursive]` on a variant
+fn is_recursive(variant: &Variant) -> Result<bool> {
+    let mut recursive = false;
+    for attr in &variant.attrs {
+        if !attr.path().is_ident("recursive") {
+            continue;
+        }
+        if recursive {
+            return Err(Error::new_spanned(attr, "duplicate #[recursive]"));
+        }
+        if !matches!(attr.meta, Meta::Path(_)) {
+            return Err(Error::new_spanned(attr, "#[recursive] takes no arguments"));
+        }
+        recursive = true;
+    }
+    Ok(recursive)
+}
+
+/// The values of `#[literal(a, b, ...)]` on a field
+fn parse_literals(field: &Field) -> Result<Option<Vec<Expr>>> {
+    let mut literals = None;
+    for attr in &field.attrs {
+        if !attr.path().is_ident("literal") {
+            continue;
+        }
+        if literals.is_some() {
+            return Err(Error::new_spanned(
+                attr,
+                "duplicate #[literal], put all the values in one",
+            ));
+        }
+        let Meta::List(ref list) = attr.meta else {
+            return Err(Error::new_spanned(
+                attr,
+                "expected #[literal(value, ...)] with the values to pick from",
+            ));
+        };
+        let values = list.parse_args_with(Punctuated::<Expr, Token![,]>::parse_terminated)?;
+        if values.is_empty() {
+            return Err(Error::new_spanned(
+                attr,
+                "#[literal()] needs at least one value",
+            ));
+        }
+        for value in &values {
+            check_literal(value, &field.ty)?;
+        }
+        literals = Some(values.into_iter().collect());
+    }
+    Ok(literals)
+}
+
+/// `#[generate_with = "path::to::fn"]`, a `fn(&mut Visitor) -> T` that generates the field or type
+fn parse_generate_with(attrs: &[Attribute]) -> Result<Option<Path>> {
+    let mut generate_with = None;
+    for attr in attrs {
+        if !attr.path().is_ident("generate_with") {
+            continue;
+        }
+        if generate_with.is_some() {
+            return Err(Error::new_spanned(attr, "duplicate #[generate_with]"));
+        }
+        let path = match &attr.meta {
+            Meta::NameValue(MetaNameValue {
+                value:
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(path),
+                        ..
+                    }),
+                ..
+            }) => path.parse::<Path>()?,
+            _ => {
+                return Err(Error::new_spanned(
+                    attr,
+                    "expected #[generate_with = \"path::to::fn\"]",
+                ))
+            }
+        };
+        generate_with = Some(path);
+    }
+    Ok(generate_with)
+}
+
+const SELF_DESCRIBING: &str = "needs a self-describing format, bincode can't read it back";
+const OTHER_TYPE: &str = "serializes another type, so the encoding doesn't follow our fields";
+const RENUMBERS: &str = "numbers the variants differently when deserializing, so our tags break";
+const ENCODED_AS_TYPE: &str = "changes the encoding, splicing expects each field encoded as its type";
+const ONE_WAY: &str = "skips the field in one direction only, use #[serde(skip)]";
+
+/// `#[serde(...)]` that move the encoding away from our fields, with why.
+/// We splice and deserialize subtrees at their offset in the encoding of the root
+/// (see `thesis::chunks`), so it has to be every field in order, each encoded as its type.
+const SERDE_CONTAINER: &[(&str, &str)] = &[
+    ("tag", SELF_DESCRIBING),
+    ("content", SELF_DESCRIBING),
+    ("untagged", SELF_DESCRIBING),
+    ("from", OTHER_TYPE),
+    ("try_from", OTHER_TYPE),
+    ("into", OTHER_TYPE),
+    (
+        "remote",
+        "implements no Serialize for the type, put #[thesis(remote = \"...\")] on a mirror instead",
+    ),
+];
+
+const SERDE_VARIANT: &[(&str, &str)] = &[
+    ("skip", RENUMBERS),
+    ("skip_serializing", RENUMBERS),
+    ("skip_deserializing", RENUMBERS),
+    ("with", ENCODED_AS_TYPE),
+    ("serialize_with", ENCODED_AS_TYPE),
+    ("deserialize_with", ENCODED_AS_TYPE),
+    ("other", SELF_DESCRIBING),
+    ("untagged", SELF_DESCRIBING),
+];
+
+const SERDE_FIELD: &[(&str, &str)] = &[
+    ("skip_serializing", ONE_WAY),
+    ("skip_deserializing", ONE_WAY),
+    (
+        "skip_serializing_if",
+        "leaves the field out of some encodings, which moves the fields after it",
+    ),
+    ("with", ENCODED_AS_TYPE),
+    ("serialize_with", ENCODED_AS_TYPE),
+    ("deserialize_with", ENCODED_AS_TYPE),
+    ("flatten", SELF_DESCRIBING),
+];
+
+/// Check `#[serde(...)]` against the `rejected` ones, returns whether the field is skipped
+/// and its `#[serde(default = "...")]`. Everything else (rename, alias, bound, ...) keeps the encoding.
+fn parse_serde(attrs: &[Attribute], rejected: &[(&str, &str)]) -> Result<(bool, Option<Path>)> {
+    let mut skip = false;
+    let mut default = None;
+    for attr in attrs {
+        if !attr.path().is_ident("serde") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            let name = meta
+                .path
+                .get_ident()
+                .map(|ident| ident.to_string())
+                .unwrap_or_default();
+            if let Some((_, why)) = rejected.iter().find(|(rejected, _)| *rejected == name) {
+                return Err(meta.error(format!("#[serde({})] {}", name, why)));
+            }
+            if meta.input.peek(Token![=]) {
+                let value = meta.value()?;
+                if name == "default" {
+                    let path: LitStr = value.parse()?;
+                    default = Some(path.parse::<Path>()?);
+                } else {
+                    value.parse::<Expr>()?;
+                }
+            } else if meta.input.peek(token::Paren) {
+                // eg. rename(serialize = "...", deserialize = "...")
+                meta.input.parse::<proc_macro2::TokenTree>()?;
+            }
+            skip |= name == "skip";
+            Ok(())
+        })?;
+    }
+    Ok((skip, default))
+}
+
+/// Our attributes only go in one place each, eg. `#[recursive]` on a field does nothing
+fn reject_attr(attrs: &[Attribute], name: &str) -> Result<()> {
+    let place = match name {
+        "recursive" => "enum variants",
+        "generate_with" => "fields and types",
+        "thesis" => "types",
+        _ => "fields",
+    };
+    match attrs.iter().find(|attr| attr.path().is_ident(name)) {
+        Some(attr) => Err(Error::new_spanned(
+            attr,
+            format!("#[{}] only goes on {}", name, place),
+        )),
+        None => Ok(()),
+    }
 }
 
 /// returns
@@ -512,59 +1064,51 @@ fn get_field_defs(nodes: &Vec<GrammarField>) -> Vec<proc_macro2::TokenStream> {
     nodes
         .iter()
         .map(|field| {
-            let attr_iterator = field.attrs.iter();
             let name = &field.name;
             let ty = &field.ty;
 
-            let mut generator = None;
-
+            if let Some(default) = &field.skip {
+                return quote! {
+                    let #name = #default;
+                };
+            }
+            // rendering overwrites it anyway
+            if field.computed {
+                return quote! {
+                    let #name = <#ty as ::std::default::Default>::default();
+                };
+            }
             // The generator can either be a closure run immediately.
             // This allows us to sepcify literals for a field.
-            // TODO: maybe do some sanitization of literals
-            for attr in attr_iterator {
-                if let Meta::List(ref list) = attr.meta {
-                    // make sure the attribute we are considering is ours.
-                    if list.path.segments.first().unwrap().ident == "literal" {
-                        let literals = list
-                            .tokens
-                            .clone()
-                            .into_iter()
-                            .filter(|i| {
-                                matches!(i, proc_macro2::TokenTree::Literal(_))
-                                    || matches!(i, proc_macro2::TokenTree::Group(_))
-                                    || matches!(i, proc_macro2::TokenTree::Ident(_))
-                            })
-                            .collect::<Vec<_>>();
-                        let literals_len = literals.len() - 1;
-                        // if we only have one literal
-                        if literals_len == 0 {
-                            let item = literals.first().unwrap();
-                            generator = Some(quote! {
-                                let #name = #item as #ty;
-                            });
-                        } else {
-                            // if we have multiple literals -> pick one randomly
-                            generator = Some(quote! {
-                                let #name = || -> #ty {
-                                    let item = v.random_range(0, #literals_len);
-                                    let literals = [#(#literals),*];
-                                    literals[item] as #ty
-                                }();
-                            });
-                        }
+            if let Some(path) = &field.generate_with {
+                return quote! {
+                    let #name = #path(v);
+                };
+            }
+            match &field.literals {
+                // if we only have one literal
+                Some(literals) if literals.len() == 1 => {
+                    let item = &literals[0];
+                    quote! {
+                        let #name = #item as #ty;
                     }
                 }
-            }
-
-            // If we did not have a literal attribute, we use the inner generate function of the type.
-            if generator.is_none() {
-                generator = Some(quote! {
+                // if we have multiple literals -> pick one randomly
+                Some(literals) => {
+                    let literals_len = literals.len() - 1;
+                    quote! {
+                        let #name = || -> #ty {
+                            let item = v.random_range(0, #literals_len);
+                            let literals = [#(#literals),*];
+                            literals[item] as #ty
+                        }();
+                    }
+                }
+                // If we did not have a literal attribute, we use the inner generate function of the type.
+                None => quote! {
                     let #name = <#ty>::generate(v, depth, cur_depth);
-                });
+                },
             }
-            // this should never happen, cause we either have a literal or not.
-            generator
-                .unwrap_or_else(|| panic!("invariant; field {:?} did not have a generator", name))
         })
         .collect::<Vec<_>>()
 }
@@ -620,14 +1164,126 @@ fn construct_generate_function_enum(
     }
 }
 
+/// The statements appending `path { a: .., b: .. }` or `path(.., ..)` to `out`, for
+/// `Node::__rust_expr`. `access` is a reference to the field.
+fn rust_expr(
+    path: String,
+    fields: &syn::Fields,
+    nodes: &Vec<GrammarField>,
+    access: impl Fn(&GrammarField) -> proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let (open, close) = match fields {
+        syn::Fields::Named(_) if nodes.is_empty() => (format!("{} {{}}", path), ""),
+        syn::Fields::Named(_) => (format!("{} {{ ", path), " }"),
+        syn::Fields::Unnamed(_) => (format!("{}(", path), ")"),
+        syn::Fields::Unit => (path, ""),
+    };
+    let is_named = matches!(fields, syn::Fields::Named(_));
+    let fields = nodes.iter().enumerate().map(|(i, field)| {
+        let separator = if i > 0 { ", " } else { "" };
+        let label = if is_named {
+            format!("{}{}: ", separator, field.name)
+        } else {
+            separator.to_string()
+        };
+        // skipped fields need not be a Node, we build them the way deserializing does
+        let value = match &field.skip {
+            Some(default) => {
+                let default = default.to_string().replace(' ', "");
+                quote! { out.push_str(#default); }
+            }
+            None => {
+                let access = access(field);
+                quote! { ::thesis::Node::__rust_expr(#access, out); }
+            }
+        };
+        quote! {
+            out.push_str(#label);
+            #value
+        }
+    });
+    quote! {
+        out.push_str(#open);
+        #(#fields)*
+        out.push_str(#close);
+    }
+}
+
 struct GrammarField {
     name: Ident,
     id: usize,
     ty: Type,
-    attrs: Vec<Attribute>,
+    /// from `#[literal(...)]`
+    literals: Option<Vec<Expr>>,
+    /// from `#[generate_with = "..."]`
+    generate_with: Option<Path>,
+    /// from `#[serde(skip)]`, how we build the field: it isn't encoded, so it's no node
+    skip: Option<proc_macro2::TokenStream>,
+    /// `#[len_of]`, `#[checksum]`, ... see `render`: the Render derive fills it in
+    computed: bool,
 }
 
 impl GrammarField {
+    /// not generated from its type
+    fn is_custom(&self) -> bool {
+        self.literals.is_some() || self.generate_with.is_some() || self.computed
+    }
+
+    /// `FieldShape` of the field for `Node::__describe`
+    fn describe(&self, is_named: bool) -> proc_macro2::TokenStream {
+        let ty = &self.ty;
+        let name = self.get_name(is_named);
+        let custom = self.is_custom();
+        let literals = self.literals.iter().flatten();
+        quote! {
+            ::thesis::lint::FieldShape::new::<#ty>(graph, stringify!(#name), #custom)
+                .with_literals(vec![#(stringify!(#literals)),*])
+        }
+    }
+
+    /// The `__mutate` arm of the field, `field` is the place (`self.name`, `*name`).
+    /// Regenerating a `#[generate_with]` field goes through its function, not its type:
+    /// what we splice or tweak in was generated by it, so that can go to the type.
+    fn mutate_arm(&self, field: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        let id = &self.id;
+        let Some(generate_with) = &self.generate_with else {
+            return quote! {
+                #id => {
+                    #field.__mutate(ty, visitor, path);
+                },
+            };
+        };
+        quote! {
+            #id => {
+                match ty {
+                    thesis::MutationType::GenerateReplace(_) | thesis::MutationType::RecursiveReplace
+                        if path.is_empty() =>
+                    {
+                        #field = #generate_with(visitor);
+                    }
+                    _ => {
+                        #field.__mutate(ty, visitor, path);
+                    }
+                }
+            },
+        }
+    }
+
+    /// mutated by the fuzzer: encoded, and not computed when rendering
+    fn is_node(&self) -> bool {
+        self.skip.is_none() && !self.computed
+    }
+
+    /// The field in an enum variant pattern, fields the arm doesn't use aren't bound
+    fn binding(&self, is_named: bool, used: bool) -> proc_macro2::TokenStream {
+        let name = &self.name;
+        match (used, is_named) {
+            (true, _) => quote! {#name},
+            (false, true) => quote! {#name: _},
+            (false, false) => quote! {_},
+        }
+    }
+
     /// If we have an unnamed tuple or struct, we need to refer to the field as an index instead of
     /// a literal.
     /// Eg: self.0, self.1 instead of self.field, self.field_two
@@ -644,6 +1300,66 @@ impl GrammarField {
     }
 }
 
+/// What a `#[literal]` can be cast to, for the primitive types we know.
+/// Anything else (consts, paths, type aliases) is left to the compiler.
+fn check_literal(literal: &Expr, ty: &Type) -> Result<()> {
+    const INTS: &[&str] = &[
+        "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+    ];
+    let ty_name = match ty {
+        Type::Path(path) if path.qself.is_none() => match path.path.get_ident() {
+            Some(ident) => ident.to_string(),
+            None => return Ok(()),
+        },
+        Type::Reference(reference) => match &*reference.elem {
+            Type::Path(path) if path.path.is_ident("str") => "&str".to_string(),
+            _ => return Ok(()),
+        },
+        _ => return Ok(()),
+    };
+    let is_int = INTS.contains(&ty_name.as_str());
+    let is_signed = is_int && ty_name.starts_with('i');
+    let is_float = ty_name == "f32" || ty_name == "f64";
+    let is_number = is_int || is_float;
+    if !is_number && !["bool", "char", "&str", "String"].contains(&ty_name.as_str()) {
+        return Ok(());
+    }
+    let casts = match literal {
+        Expr::Lit(ExprLit { lit, .. }) => match lit {
+            Lit::Int(int) => is_number || (ty_name == "char" && int.base10_parse::<u8>().is_ok()),
+            Lit::Float(_) => is_number,
+            Lit::Bool(_) => is_int || ty_name == "bool",
+            Lit::Char(_) => is_int || ty_name == "char",
+            Lit::Byte(_) => is_number || ty_name == "char",
+            Lit::Str(_) => ty_name == "&str",
+            _ => return Ok(()),
+        },
+        // an unsuffixed -1 is typed as the field, so it has to be signed
+        Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr,
+            ..
+        }) => match &**expr {
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(_), ..
+            }) => is_signed || is_float,
+            Expr::Lit(ExprLit {
+                lit: Lit::Float(_), ..
+            }) => is_number,
+            _ => return Ok(()),
+        },
+        _ => return Ok(()),
+    };
+    if casts {
+        Ok(())
+    } else {
+        Err(Error::new_spanned(
+            literal,
+            format!("#[literal] value can't be cast to {}", ty_name),
+        ))
+    }
+}
+
 fn get_nodes(fields: &syn::Fields) -> Option<&syn::punctuated::Punctuated<syn::Field, Comma>> {
     match fields {
         syn::Fields::Unnamed(FieldsUnnamed { ref unnamed, .. }) => Some(unnamed),
@@ -661,13 +1377,23 @@ fn type_to_nautilus(ty: &syn::Type) -> String {
 #[proc_macro_derive(ToNautilus)]
 pub fn to_nautilus(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let parsed = syn::parse_macro_input!(input as syn::DeriveInput);
+    derive_nautilus(parsed)
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
+}
+
+fn derive_nautilus(parsed: DeriveInput) -> Result<proc_macro2::TokenStream> {
     let root_name = parsed.ident;
     let expanded = match parsed.data {
         syn::Data::Struct(ref data) => {
-            let nodes = get_nodes(&data.fields)
-                .expect("Structs cannot have no nodes according to borsh!");
+            let nodes = get_nodes(&data.fields).ok_or_else(|| {
+                Error::new(
+                    root_name.span(),
+                    "ToNautilus can't be derived for unit structs, they have no nodes",
+                )
+            })?;
             let is_named = matches!(data.fields, syn::Fields::Named(_));
-            let parsed = parse_nodes(nodes);
+            let parsed = parse_nodes(nodes)?;
             let nodes = parsed.iter().map(|field| {
                 let name = field.get_name(is_named);
                 let ty = type_to_nautilus(&field.ty);
@@ -722,7 +1448,12 @@ pub fn to_nautilus(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
                 }
             }
         }
-        _ => panic!("we do not support unions!"),
+        syn::Data::Union(ref data) => {
+            return Err(Error::new(
+                data.union_token.span(),
+                "ToNautilus can't be derived for unions",
+            ));
+        }
     };
-    TokenStream::from(expanded)
+    Ok(expanded)
 }
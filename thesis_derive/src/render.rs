@@ -0,0 +1,370 @@
+//! `#[derive(Render)]`: fields rendered one after the other, with the computed ones
+//! (`#[len_of]`, `#[count_of]`, `#[offset_of]`, `#[checksum]`) filled in afterwards.
+use quote::{format_ident, quote};
+use syn::{punctuated::Punctuated, spanned::Spanned, token::Comma, *};
+
+/// Attributes of fields the fuzzer doesn't mutate, since rendering overwrites them
+const COMPUTED: &[&str] = &["len_of", "count_of", "offset_of", "checksum"];
+
+/// Used by the Grammar derive to leave the field out of mutation
+pub(crate) fn is_computed(field: &Field) -> bool {
+    field
+        .attrs
+        .iter()
+        .any(|attr| COMPUTED.iter().any(|name| attr.path().is_ident(name)))
+}
+
+/// What a computed field holds, the members are fields of the same struct (or variant)
+enum Computed {
+    Len(Member),
+    Count(Member),
+    Offset(Member),
+    Checksum { algorithm: Path, over: Vec<Member> },
+}
+
+/// `#[be]` / `#[le]`
+#[derive(Clone, Copy)]
+enum Order {
+    Big,
+    Little,
+}
+
+fn parse_order(attrs: &[Attribute]) -> Result<Option<Order>> {
+    let mut order = None;
+    for attr in attrs {
+        let this = if attr.path().is_ident("be") {
+            Order::Big
+        } else if attr.path().is_ident("le") {
+            Order::Little
+        } else {
+            continue;
+        };
+        if order.is_some() {
+            return Err(Error::new_spanned(attr, "only one of #[be] and #[le]"));
+        }
+        if !matches!(attr.meta, Meta::Path(_)) {
+            return Err(Error::new_spanned(
+                attr,
+                "#[be] and #[le] take no arguments",
+            ));
+        }
+        order = Some(this);
+    }
+    Ok(order)
+}
+
+fn parse_computed(field: &Field) -> Result<Option<Computed>> {
+    let mut computed = None;
+    for attr in &field.attrs {
+        let path = attr.path();
+        if !COMPUTED.iter().any(|name| path.is_ident(name)) {
+            continue;
+        }
+        if computed.is_some() {
+            return Err(Error::new_spanned(
+                attr,
+                "a field holds one computed value, only one of #[len_of], #[count_of], #[offset_of] and #[checksum]",
+            ));
+        }
+        computed = Some(if path.is_ident("len_of") {
+            Computed::Len(attr.parse_args()?)
+        } else if path.is_ident("count_of") {
+            Computed::Count(attr.parse_args()?)
+        } else if path.is_ident("offset_of") {
+            Computed::Offset(attr.parse_args()?)
+        } else {
+            parse_checksum(attr)?
+        });
+    }
+    Ok(computed)
+}
+
+/// `#[checksum(crc32, over = field)]` or `over = (a, b, ...)`
+fn parse_checksum(attr: &Attribute) -> Result<Computed> {
+    let mut algorithm = None;
+    let mut over = None;
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("over") {
+            if over.is_some() {
+                return Err(meta.error("duplicate over"));
+            }
+            let value = meta.value()?;
+            over = Some(if value.peek(token::Paren) {
+                let content;
+                parenthesized!(content in value);
+                Punctuated::<Member, Comma>::parse_terminated(&content)?
+                    .into_iter()
+                    .collect()
+            } else {
+                vec![value.parse::<Member>()?]
+            });
+        } else if meta.input.is_empty() || meta.input.peek(Comma) {
+            if algorithm.is_some() {
+                return Err(meta.error("duplicate checksum algorithm"));
+            }
+            algorithm = Some(meta.path);
+        } else {
+            return Err(
+                meta.error("expected #[checksum(crc32 | adler32 | path::to::fn, over = ...)]")
+            );
+        }
+        Ok(())
+    })?;
+    let algorithm = algorithm.ok_or_else(|| {
+        Error::new_spanned(
+            attr,
+            "#[checksum] needs an algorithm: crc32, adler32 or path::to::fn",
+        )
+    })?;
+    let over = over.ok_or_else(|| {
+        Error::new_spanned(
+            attr,
+            "#[checksum] needs the fields it covers: over = field or over = (a, b)",
+        )
+    })?;
+    if over.is_empty() {
+        return Err(Error::new_spanned(attr, "#[checksum] over no fields"));
+    }
+    Ok(Computed::Checksum { algorithm, over })
+}
+
+/// Numbers, or Vecs, arrays, Options and Boxes of them: what `#[be]` / `#[le]` on a type
+/// applies to. Anything else renders with its own `Render`.
+fn holds_numbers(ty: &Type) -> bool {
+    const NUMBERS: &[&str] = &[
+        "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+        "f32", "f64",
+    ];
+    match ty {
+        Type::Array(array) => holds_numbers(&array.elem),
+        Type::Path(path) if path.qself.is_none() => {
+            let Some(last) = path.path.segments.last() else {
+                return false;
+            };
+            match &last.arguments {
+                PathArguments::None => NUMBERS.iter().any(|number| last.ident == number),
+                PathArguments::AngleBracketed(arguments)
+                    if ["Vec", "Option", "Box"]
+                        .iter()
+                        .any(|wrapper| last.ident == wrapper) =>
+                {
+                    match arguments.args.first() {
+                        Some(GenericArgument::Type(inner)) => holds_numbers(inner),
+                        _ => false,
+                    }
+                }
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Our attributes only go on fields (and `#[be]` / `#[le]` on types and variants too)
+fn reject_computed(attrs: &[Attribute]) -> Result<()> {
+    match attrs
+        .iter()
+        .find(|attr| COMPUTED.iter().any(|name| attr.path().is_ident(name)))
+    {
+        Some(attr) => Err(Error::new_spanned(
+            attr,
+            "computed values only go on fields",
+        )),
+        None => Ok(()),
+    }
+}
+
+/// The `render` body for `fields`, `access` is a reference to each field
+/// (`&self.name` in a struct, the binding in an enum arm).
+/// Our locals start with `__render_` so they don't shadow the bindings.
+fn render_fields(
+    fields: &Fields,
+    access: &[proc_macro2::TokenStream],
+    default_order: Option<Order>,
+) -> Result<proc_macro2::TokenStream> {
+    let fields = fields.iter().collect::<Vec<_>>();
+    let bytes = (0..fields.len())
+        .map(|i| format_ident!("__render_{}", i))
+        .collect::<Vec<_>>();
+    // the index of the field `member` names, not `this` itself
+    let find = |member: &Member, this: usize| -> Result<usize> {
+        let found = fields
+            .iter()
+            .enumerate()
+            .position(|(i, field)| match member {
+                Member::Named(name) => field.ident.as_ref() == Some(name),
+                Member::Unnamed(index) => index.index as usize == i,
+            });
+        match found {
+            Some(i) if i == this => Err(Error::new_spanned(
+                member,
+                "a field can't be computed from itself",
+            )),
+            Some(i) => Ok(i),
+            None => Err(Error::new_spanned(member, "no such field here")),
+        }
+    };
+
+    let mut first = vec![];
+    let mut lengths = vec![];
+    let mut checksums = vec![];
+    for (i, field) in fields.iter().enumerate() {
+        let ty = &field.ty;
+        let out = &bytes[i];
+        let order = match parse_order(&field.attrs)? {
+            Some(order) => Some(order),
+            None if holds_numbers(ty) => default_order,
+            None => None,
+        };
+        let render = |value: proc_macro2::TokenStream| match order {
+            Some(Order::Big) => quote! { ::thesis::render::Endian::render_be(#value, &mut #out); },
+            Some(Order::Little) => {
+                quote! { ::thesis::render::Endian::render_le(#value, &mut #out); }
+            }
+            None => quote! { ::thesis::render::Render::render(#value, &mut #out); },
+        };
+        let Some(computed) = parse_computed(field)? else {
+            let render = render(access[i].clone());
+            first.push(quote! {
+                let mut #out = vec![];
+                #render
+            });
+            continue;
+        };
+        // computed fields are fixed width, so rendering 0 first tells the others where they go
+        let render = render(quote! { &__render_value });
+        first.push(quote! {
+            let mut #out = vec![];
+            let __render_value = <#ty as ::thesis::render::FromComputed>::from_computed(0);
+            #render
+        });
+        let value = match computed {
+            Computed::Len(member) => {
+                let of = &bytes[find(&member, i)?];
+                quote! { #of.len() as u64 }
+            }
+            Computed::Count(member) => {
+                let of = &access[find(&member, i)?];
+                quote! { (#of).len() as u64 }
+            }
+            Computed::Offset(member) => {
+                let before = &bytes[..find(&member, i)?];
+                quote! { (0usize #(+ #before.len())*) as u64 }
+            }
+            Computed::Checksum { algorithm, over } => {
+                let over = over
+                    .iter()
+                    .map(|member| find(member, i).map(|j| &bytes[j]))
+                    .collect::<Result<Vec<_>>>()?;
+                let algorithm = if algorithm.is_ident("crc32") || algorithm.is_ident("adler32") {
+                    quote! { ::thesis::render::#algorithm }
+                } else {
+                    quote! { #algorithm }
+                };
+                checksums.push(quote! {
+                    let mut __render_over = vec![];
+                    #(__render_over.extend_from_slice(&#over);)*
+                    let __render_value = <#ty as ::thesis::render::FromComputed>::from_computed(
+                        #algorithm(&__render_over) as u64
+                    );
+                    #out.clear();
+                    #render
+                });
+                continue;
+            }
+        };
+        lengths.push(quote! {
+            let __render_value = <#ty as ::thesis::render::FromComputed>::from_computed(#value);
+            #out.clear();
+            #render
+        });
+    }
+    // checksums last and in field order, so they cover the final lengths (and checksums above)
+    Ok(quote! {
+        #(#first)*
+        #({ #lengths })*
+        #({ #checksums })*
+        #(__render_out.extend_from_slice(&#bytes);)*
+    })
+}
+
+pub(crate) fn derive_render(parsed: DeriveInput) -> Result<proc_macro2::TokenStream> {
+    reject_computed(&parsed.attrs)?;
+    let order = parse_order(&parsed.attrs)?;
+    let root_name = &parsed.ident;
+    let body = match &parsed.data {
+        Data::Struct(data) => {
+            let access = data
+                .fields
+                .iter()
+                .enumerate()
+                .map(|(i, field)| match &field.ident {
+                    Some(name) => quote! { &self.#name },
+                    None => {
+                        let index = Index::from(i);
+                        quote! { &self.#index }
+                    }
+                })
+                .collect::<Vec<_>>();
+            render_fields(&data.fields, &access, order)?
+        }
+        Data::Enum(data) => {
+            let mut arms = vec![];
+            for variant in &data.variants {
+                reject_computed(&variant.attrs)?;
+                let order = parse_order(&variant.attrs)?.or(order);
+                let variant_name = &variant.ident;
+                let names = variant
+                    .fields
+                    .iter()
+                    .enumerate()
+                    .map(|(i, field)| match &field.ident {
+                        Some(name) => name.clone(),
+                        None => format_ident!("_{}", i),
+                    })
+                    .collect::<Vec<_>>();
+                let access = names
+                    .iter()
+                    .map(|name| quote! { #name })
+                    .collect::<Vec<_>>();
+                let body = render_fields(&variant.fields, &access, order)?;
+                // we only render the value we compute, not the field
+                let bindings = variant.fields.iter().zip(&names).map(|(field, name)| {
+                    match (is_computed(field), &field.ident) {
+                        (false, _) => quote! { #name },
+                        (true, Some(_)) => quote! { #name: _ },
+                        (true, None) => quote! { _ },
+                    }
+                });
+                let pattern = match &variant.fields {
+                    Fields::Named(_) => quote! { {#(#bindings),*} },
+                    Fields::Unnamed(_) => quote! { (#(#bindings),*) },
+                    Fields::Unit => quote! {},
+                };
+                arms.push(quote! {
+                    Self::#variant_name #pattern => {
+                        #body
+                    }
+                });
+            }
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        Data::Union(data) => {
+            return Err(Error::new(
+                data.union_token.span(),
+                "Render can't be derived for unions, use an enum",
+            ));
+        }
+    };
+    Ok(quote! {
+        impl ::thesis::render::Render for #root_name {
+            fn render(&self, __render_out: &mut ::std::vec::Vec<u8>) {
+                #body
+            }
+        }
+    })
+}
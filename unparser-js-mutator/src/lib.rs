@@ -0,0 +1,25 @@
+//! AFL_CUSTOM_MUTATOR_LIBRARY / libFuzzer custom mutator for the js grammar.
+mod ast;
+mod render;
+use ast::Statement;
+use libafl_fuzzer::impl_converter;
+use serde::{Deserialize, Serialize};
+use thesis::Node;
+#[derive(Serialize, Deserialize, thesis::Grammar, thesis::ToNautilus, Clone, Debug)]
+pub struct Code {
+    data: Vec<Statement>,
+}
+
+impl_converter!(Code, |data: Code| {
+    if data.data.len() == 0 {
+        "console".as_bytes().to_vec()
+    } else {
+        let res = data.data
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<String>();
+        res.as_bytes().to_vec()
+    }
+});
+
+custom_mutator::export_mutator!(Code, FuzzDataTargetBytesConverter);
@@ -5,6 +5,13 @@ use crate::ast::{
 use std::fmt::{self, Display};
 impl fmt::Display for Expression {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // deeply nested inputs would overflow the stack
+        thesis::grow(|| self.render(f))
+    }
+}
+
+impl Expression {
+    fn render(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Expression::Literal(value) => match value {
                 LiteralValue::Number(n) => write!(f, "{}", n),
@@ -166,6 +173,13 @@ impl fmt::Display for UnaryOperator {
 
 impl fmt::Display for Statement {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // deeply nested inputs would overflow the stack
+        thesis::grow(|| self.render(f))
+    }
+}
+
+impl Statement {
+    fn render(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Statement::If(condition, then_branch, else_if_branches, else_branch) => {
                 let mut ret = format!("if ({}) {{\n{}\n}}\n", condition, then_branch);
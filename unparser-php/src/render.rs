@@ -2,6 +2,13 @@ use crate::ast::*;
 use std::fmt;
 impl fmt::Display for Statement {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // deeply nested inputs would overflow the stack
+        thesis::grow(|| self.render(f))
+    }
+}
+
+impl Statement {
+    fn render(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let code = match self {
             Statement::Expression(expr) => format!("{};", expr),
             Statement::Assignment(typ, left, right) => {
@@ -134,6 +141,13 @@ impl fmt::Display for Statement {
 
 impl fmt::Display for Expression {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // deeply nested inputs would overflow the stack
+        thesis::grow(|| self.render(f))
+    }
+}
+
+impl Expression {
+    fn render(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let code = match self {
             Expression::Literal(value) => value.to_string(),
             Expression::Variable(name) => format!("${}", name),
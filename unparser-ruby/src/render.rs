@@ -3,6 +3,13 @@ use std::fmt;
 
 impl fmt::Display for Statement {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // deeply nested inputs would overflow the stack
+        thesis::grow(|| self.render(f))
+    }
+}
+
+impl Statement {
+    fn render(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let output = match self {
             Statement::Assignment(lhs, rhs) => format!("{} = {}", lhs, rhs),
             Statement::GlobalAssignment(name, value) => format!("{} = {}", name, value),
@@ -138,6 +145,13 @@ impl fmt::Display for Statement {
 
 impl fmt::Display for Expression {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // deeply nested inputs would overflow the stack
+        thesis::grow(|| self.render(f))
+    }
+}
+
+impl Expression {
+    fn render(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let output = match self {
             Expression::Literal(value) => value.to_string(),
             Expression::Variable(name) => name.clone(),